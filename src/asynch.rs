@@ -0,0 +1,7 @@
+//! Async driver, built on the embedded-hal-async [`I2c`](embedded_hal_async::i2c::I2c) and
+//! [`DelayNs`](embedded_hal_async::delay::DelayNs) traits
+//!
+//! Offers the same API as the blocking driver, with every method that talks to the device
+//! being an `async fn`.
+
+pub use crate::device::{SensorArrayAsync as SensorArray, VL53L0XAsync as VL53L0X};
@@ -0,0 +1,105 @@
+//! Calibration data, to be stored and restored instead of calibrating on every boot
+
+use crate::{RefCalibration, ReferenceSpads};
+
+const CALIBRATION_DATA_VERSION: u8 = 1;
+
+/// Results of the device calibrations, as returned by `VL53L0X::get_calibration_data` and applied
+/// by `VL53L0X::set_calibration_data`
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct CalibrationData {
+    /// Reference SPADs selection, as described by `ref_spad_map`
+    pub reference_spads: ReferenceSpads,
+    /// Map of the enabled reference SPADs (GLOBAL_CONFIG_SPAD_ENABLES_REF_0 to _5)
+    pub ref_spad_map: [u8; 6],
+    /// VHV and phase reference calibration values
+    pub ref_calibration: RefCalibration,
+    /// Range offset in micrometers
+    pub offset_um: i32,
+    /// Crosstalk compensation rate per SPAD, in MCPS
+    pub xtalk_compensation_rate_mcps: f32,
+    /// Whether the crosstalk compensation is enabled
+    pub xtalk_compensation_enabled: bool,
+}
+
+/// Defines errors decoding calibration data bytes
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum CalibrationDataError {
+    /// Fewer bytes than `CalibrationData::SIZE`
+    InvalidLength,
+    /// Encoding version not supported by this driver (returned value is argument)
+    UnsupportedVersion(u8),
+    /// Checksum does not match, the bytes are corrupted
+    InvalidChecksum,
+}
+
+impl CalibrationData {
+    /// Size of the byte encoding
+    pub const SIZE: usize = 21;
+
+    /// Encodes the calibration data into bytes: a version byte, the fields in little endian
+    /// order and a CRC-8 checksum of the preceding bytes
+    pub fn to_bytes(&self) -> [u8; Self::SIZE] {
+        let mut bytes = [0; Self::SIZE];
+        bytes[0] = CALIBRATION_DATA_VERSION;
+        bytes[1] = self.reference_spads.count;
+        bytes[2] = self.reference_spads.is_aperture as u8;
+        bytes[3..9].copy_from_slice(&self.ref_spad_map);
+        bytes[9] = self.ref_calibration.vhv_settings;
+        bytes[10] = self.ref_calibration.phase_cal;
+        bytes[11..15].copy_from_slice(&self.offset_um.to_le_bytes());
+        bytes[15..19].copy_from_slice(&self.xtalk_compensation_rate_mcps.to_bits().to_le_bytes());
+        bytes[19] = self.xtalk_compensation_enabled as u8;
+        bytes[20] = crc8(&bytes[..20]);
+        bytes
+    }
+
+    /// Decodes calibration data encoded by `to_bytes`; extra bytes are ignored
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, CalibrationDataError> {
+        if bytes.len() < Self::SIZE {
+            return Err(CalibrationDataError::InvalidLength);
+        }
+        if bytes[0] != CALIBRATION_DATA_VERSION {
+            return Err(CalibrationDataError::UnsupportedVersion(bytes[0]));
+        }
+        if crc8(&bytes[..20]) != bytes[20] {
+            return Err(CalibrationDataError::InvalidChecksum);
+        }
+
+        let mut ref_spad_map = [0; 6];
+        ref_spad_map.copy_from_slice(&bytes[3..9]);
+
+        Ok(CalibrationData {
+            reference_spads: ReferenceSpads {
+                count: bytes[1],
+                is_aperture: bytes[2] != 0,
+            },
+            ref_spad_map,
+            ref_calibration: RefCalibration {
+                vhv_settings: bytes[9],
+                phase_cal: bytes[10],
+            },
+            offset_um: i32::from_le_bytes([bytes[11], bytes[12], bytes[13], bytes[14]]),
+            xtalk_compensation_rate_mcps: f32::from_bits(u32::from_le_bytes([
+                bytes[15], bytes[16], bytes[17], bytes[18],
+            ])),
+            xtalk_compensation_enabled: bytes[19] != 0,
+        })
+    }
+}
+
+// CRC-8, polynomial 0x07
+fn crc8(bytes: &[u8]) -> u8 {
+    let mut crc: u8 = 0;
+    for byte in bytes {
+        crc ^= byte;
+        for _ in 0..8 {
+            crc = if crc & 0x80 != 0 {
+                (crc << 1) ^ 0x07
+            } else {
+                crc << 1
+            };
+        }
+    }
+    crc
+}
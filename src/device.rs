@@ -0,0 +1,1556 @@
+//! Driver front-end, shared by the blocking and the async API
+//!
+//! The register sequences are written once as `async` code; the blocking [`VL53L0X`] is
+//! generated from it by stripping the `.await`s, and `VL53L0XAsync` (exported as
+//! `asynch::VL53L0X`) keeps them when the `async` feature is enabled.
+
+mod array;
+mod calibration;
+mod interrupt;
+mod power;
+
+pub use self::array::SensorArray;
+#[cfg(feature = "async")]
+pub use self::array::SensorArrayAsync;
+
+use core::marker::PhantomData;
+use core::time::Duration;
+
+use ehal::delay::DelayNs;
+use ehal::digital::InputPin;
+use ehal::i2c::{I2c, Operation, SevenBitAddress};
+#[cfg(feature = "async")]
+use embedded_hal_async::{delay::DelayNs as AsyncDelayNs, digital::Wait, i2c::I2c as AsyncI2c};
+
+use crate::mode::sealed::Sealed;
+use crate::{
+    decode_q9_7, decode_timeout, decode_vcsel_period, encode_q9_7, encode_timeout,
+    encode_vcsel_period, final_range_timeout_mclks, limit_check::LimitChecks,
+    timeout_mclks_to_microseconds, timeout_microseconds_to_mclks, CalibrationData, Continuous,
+    Error, Idle, InterruptMode, InterruptPolarity, LimitCheck, Ranging, RangingMeasurement,
+    RangingProfile, ReferenceSpads, Register, SeqStepEnables, SeqStepTimeouts, SequenceStep, Timed,
+    VcselPeriodType, ADDRESS_DEFAULT,
+};
+
+#[maybe_async_cfg::maybe(
+    sync(keep_self),
+    async(feature = "async", keep_self),
+    idents(I2c(sync, async = "AsyncI2c"), VL53L0X(sync, async = "VL53L0XAsync"))
+)]
+/// Struct for VL53L0X
+///
+/// `MODE` tracks whether the sensor is [`Idle`] or ranging continuously ([`Continuous`] or
+/// [`Timed`]), so that only the readings valid in that mode can be taken.
+#[derive(Debug, Copy, Clone)]
+pub struct VL53L0X<I2C, D, MODE = Idle, XSHUT = ()> {
+    com: I2C,
+    delay: D,
+    // XSHUT pin, or () when the driver does not control it
+    xshut: XSHUT,
+    // calibration data applied instead of calibrating, at init and after a power cycle
+    calibration: Option<CalibrationData>,
+    // 0 waits forever
+    io_timeout_microseconds: u32,
+    io_mode2v8: bool,
+    stop_variable: u8,
+    measurement_timing_budget_microseconds: u32,
+    address: u8,
+    // sequence step timeouts as of the last timing budget change, for the sigma estimate
+    timeouts: SeqStepTimeouts,
+    limit_checks: LimitChecks,
+    // crosstalk compensation rate per SPAD, in MCPS; written to the device only while enabled
+    xtalk_compensation_rate_mcps: f32,
+    xtalk_compensation_enabled: bool,
+    // good SPAD map as read at boot, the reference SPADs are chosen among these
+    ref_good_spad_map: [u8; 6],
+    interrupt_polarity: InterruptPolarity,
+    // inter-measurement period of the last timed ranging, to start it again after a power cycle
+    intermeasurement_period_millis: u32,
+    mode: PhantomData<MODE>,
+}
+
+#[maybe_async_cfg::maybe(
+    sync(keep_self),
+    async(feature = "async"),
+    idents(
+        I2c(sync, async = "AsyncI2c"),
+        DelayNs(sync, async = "AsyncDelayNs"),
+        VL53L0X(sync, async = "VL53L0XAsync")
+    )
+)]
+impl<I2C, D, E> VL53L0X<I2C, D>
+where
+    I2C: I2c<SevenBitAddress, Error = E>,
+    D: DelayNs,
+{
+    /// Creates a sensor with default configuration (address 0x29, 2V8 I/O mode)
+    ///
+    /// The delay is used to sleep between polls of the device while waiting for it.
+    pub async fn default(i2c: I2C, delay: D) -> Result<VL53L0X<I2C, D>, Error<E>> {
+        VL53L0X::new(i2c, delay, ADDRESS_DEFAULT, true).await
+    }
+
+    /// Creates a sensor with specific configuration
+    ///
+    /// The bus can be borrowed by passing `&mut i2c`, or shared with other devices through an
+    /// embedded-hal-bus device such as `RefCellDevice`, `CriticalSectionDevice` or `MutexDevice`.
+    pub async fn new(
+        i2c: I2C,
+        delay: D,
+        address: u8,
+        io_mode2v8: bool,
+    ) -> Result<VL53L0X<I2C, D>, Error<E>> {
+        let mut chip = VL53L0X::from_parts(i2c, delay, (), address, io_mode2v8, None);
+        chip.init().await?;
+        Ok(chip)
+    }
+
+    /// Creates a sensor with specific configuration, applying calibration data returned by a
+    /// previous `get_calibration_data` instead of running the reference SPAD and reference
+    /// calibrations. Returns `Error::InvalidXtalkCompensationRate` or `Error::InvalidOffset` if the
+    /// calibration data holds an invalid crosstalk compensation rate or offset.
+    pub async fn new_with_calibration(
+        i2c: I2C,
+        delay: D,
+        address: u8,
+        io_mode2v8: bool,
+        calibration: &CalibrationData,
+    ) -> Result<VL53L0X<I2C, D>, Error<E>> {
+        let mut chip = VL53L0X::from_parts(i2c, delay, (), address, io_mode2v8, Some(*calibration));
+        chip.init().await?;
+        Ok(chip)
+    }
+}
+
+#[maybe_async_cfg::maybe(
+    sync(keep_self),
+    async(feature = "async"),
+    idents(
+        I2c(sync, async = "AsyncI2c"),
+        DelayNs(sync, async = "AsyncDelayNs"),
+        InputPin(sync, async = "Wait"),
+        VL53L0X(sync, async = "VL53L0XAsync")
+    )
+)]
+impl<I2C, D, MODE, XSHUT, E> VL53L0X<I2C, D, MODE, XSHUT>
+where
+    I2C: I2c<SevenBitAddress, Error = E>,
+    D: DelayNs,
+{
+    // Creates the driver without talking to the device
+    fn from_parts(
+        i2c: I2C,
+        delay: D,
+        xshut: XSHUT,
+        address: u8,
+        io_mode2v8: bool,
+        calibration: Option<CalibrationData>,
+    ) -> VL53L0X<I2C, D, MODE, XSHUT> {
+        VL53L0X {
+            com: i2c,
+            delay,
+            xshut,
+            calibration,
+            io_timeout_microseconds: DEFAULT_IO_TIMEOUT_MICROSECONDS,
+            io_mode2v8,
+            stop_variable: 0,
+            measurement_timing_budget_microseconds: 0,
+            address,
+            timeouts: SeqStepTimeouts::default(),
+            limit_checks: LimitChecks::default(),
+            xtalk_compensation_rate_mcps: 0.0,
+            xtalk_compensation_enabled: false,
+            ref_good_spad_map: [0; 6],
+            interrupt_polarity: InterruptPolarity::ActiveLow,
+            intermeasurement_period_millis: 0,
+            mode: PhantomData,
+        }
+    }
+
+    // Moves the driver to another ranging mode, once the device has been switched to it
+    fn into_mode<M>(self) -> VL53L0X<I2C, D, M, XSHUT> {
+        VL53L0X {
+            com: self.com,
+            delay: self.delay,
+            xshut: self.xshut,
+            calibration: self.calibration,
+            io_timeout_microseconds: self.io_timeout_microseconds,
+            io_mode2v8: self.io_mode2v8,
+            stop_variable: self.stop_variable,
+            measurement_timing_budget_microseconds: self.measurement_timing_budget_microseconds,
+            address: self.address,
+            timeouts: self.timeouts,
+            limit_checks: self.limit_checks,
+            xtalk_compensation_rate_mcps: self.xtalk_compensation_rate_mcps,
+            xtalk_compensation_enabled: self.xtalk_compensation_enabled,
+            ref_good_spad_map: self.ref_good_spad_map,
+            interrupt_polarity: self.interrupt_polarity,
+            intermeasurement_period_millis: self.intermeasurement_period_millis,
+            mode: PhantomData,
+        }
+    }
+
+    // Waits for the device to boot, then initializes it
+    async fn init(&mut self) -> Result<(), Error<E>> {
+        self.wait_boot().await?;
+        self.init_hardware().await
+    }
+
+    // Polls WHO_AM_I until the device answers; bus errors are expected while it boots
+    async fn wait_boot(&mut self) -> Result<(), Error<E>> {
+        let mut waited_microseconds = 0;
+        loop {
+            let wai = self.who_am_i().await;
+            if let Ok(0xEE) = wai {
+                return Ok(());
+            }
+            if self.poll_delay(&mut waited_microseconds).await.is_err() {
+                return match wai {
+                    Ok(wai) => Err(Error::InvalidDevice(wai)),
+                    Err(e) => Err(Error::BusError(e)),
+                };
+            }
+        }
+    }
+
+    /// Sets how long the driver waits for the device, e.g. for a measurement, before returning
+    /// `Error::Timeout`. Defaults to 500 ms; a zero timeout waits forever.
+    ///
+    /// The time is counted from the delays between polls, so the bus transfers make the actual
+    /// timeout slightly longer.
+    pub fn set_io_timeout(&mut self, timeout: Duration) {
+        self.io_timeout_microseconds = timeout.as_micros().min(u32::MAX as u128) as u32;
+    }
+
+    /// Returns how long the driver waits for the device before returning `Error::Timeout`
+    pub fn io_timeout(&self) -> Duration {
+        Duration::from_micros(self.io_timeout_microseconds as u64)
+    }
+
+    /// Destroys the driver and returns the I2C bus; the delay and XSHUT pin are dropped
+    pub fn release(self) -> I2C {
+        self.com
+    }
+
+    /// Destroys the driver and returns the I2C bus, the delay and the XSHUT pin
+    pub fn into_parts(self) -> (I2C, D, XSHUT) {
+        (self.com, self.delay, self.xshut)
+    }
+
+    async fn read_register(&mut self, reg: Register) -> Result<u8, E> {
+        self.read_byte(reg as u8).await
+    }
+
+    async fn read_byte(&mut self, reg: u8) -> Result<u8, E> {
+        let mut data: [u8; 1] = [0];
+        self.com.write_read(self.address, &[reg], &mut data).await?;
+        Ok(data[0])
+    }
+
+    async fn read_6bytes(&mut self, reg: Register) -> Result<[u8; 6], E> {
+        self.read_registers(reg).await
+    }
+
+    async fn read_registers<const N: usize>(&mut self, reg: Register) -> Result<[u8; N], E> {
+        let mut buffer = [0; N];
+        self.com
+            .write_read(self.address, &[reg as u8], &mut buffer)
+            .await?;
+        Ok(buffer)
+    }
+
+    async fn read_16bit(&mut self, reg: Register) -> Result<u16, E> {
+        let buffer: [u8; 2] = self.read_registers(reg).await?;
+        Ok(u16::from_be_bytes(buffer))
+    }
+
+    async fn write_byte(&mut self, reg: u8, byte: u8) -> Result<(), E> {
+        self.com.write(self.address, &[reg, byte]).await
+    }
+
+    async fn write_register(&mut self, reg: Register, byte: u8) -> Result<(), E> {
+        self.write_byte(reg as u8, byte).await
+    }
+
+    async fn write_registers(&mut self, reg: Register, bytes: &[u8]) -> Result<(), E> {
+        // adjacent write operations are sent back to back, without a repeated start
+        self.com
+            .transaction(
+                self.address,
+                &mut [Operation::Write(&[reg as u8]), Operation::Write(bytes)],
+            )
+            .await
+    }
+
+    async fn write_6bytes(&mut self, reg: Register, bytes: [u8; 6]) -> Result<(), E> {
+        self.write_registers(reg, &bytes).await
+    }
+
+    async fn write_16bit(&mut self, reg: Register, word: u16) -> Result<(), E> {
+        self.write_registers(reg, &word.to_be_bytes()).await
+    }
+
+    async fn write_32bit(&mut self, reg: Register, word: u32) -> Result<(), E> {
+        self.write_registers(reg, &word.to_be_bytes()).await
+    }
+
+    // Sets the final range signal rate limit and enables its check
+    async fn apply_signal_rate_limit(&mut self, limit: f32) -> Result<(), Error<E>> {
+        self.apply_limit_check_value(LimitCheck::SignalRateFinalRange, limit)
+            .await?;
+        if !self.limit_checks.enabled(LimitCheck::SignalRateFinalRange) {
+            self.apply_limit_check_enable(LimitCheck::SignalRateFinalRange, true)
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Returns the return signal rate limit of the final range step in MCPS, as read from the
+    /// device; 0 when the limit check is disabled
+    pub async fn signal_rate_limit(&mut self) -> Result<f32, E> {
+        Ok(decode_q9_7(
+            self.read_16bit(Register::FINAL_RANGE_CONFIG_MIN_COUNT_RATE_RTN_LIMIT)
+                .await?,
+        ))
+    }
+
+    // Enables or disables a limit check, on the device or in the driver
+    async fn apply_limit_check_enable(
+        &mut self,
+        check: LimitCheck,
+        enable: bool,
+    ) -> Result<(), Error<E>> {
+        match check {
+            LimitCheck::SignalRateFinalRange => {
+                // the device disables the check when the limit is 0
+                let limit = if enable {
+                    encode_q9_7(self.limit_checks.value(check))
+                } else {
+                    0
+                };
+                self.write_16bit(Register::FINAL_RANGE_CONFIG_MIN_COUNT_RATE_RTN_LIMIT, limit)
+                    .await?;
+            }
+            LimitCheck::SignalRateMsrc | LimitCheck::SignalRatePreRange => {
+                // a set bit disables the check
+                let bit = msrc_config_control_bit(check);
+                let config = self.read_register(Register::MSRC_CONFIG_CONTROL).await?;
+                let config = if enable { config & !bit } else { config | bit };
+                self.write_register(Register::MSRC_CONFIG_CONTROL, config)
+                    .await?;
+            }
+            _ => {}
+        }
+        self.limit_checks.enables[check as usize] = enable;
+
+        Ok(())
+    }
+
+    /// Returns whether a limit check is enabled
+    pub async fn get_limit_check_enable(&mut self, check: LimitCheck) -> Result<bool, E> {
+        match check {
+            LimitCheck::SignalRateMsrc | LimitCheck::SignalRatePreRange => {
+                let config = self.read_register(Register::MSRC_CONFIG_CONTROL).await?;
+                Ok(config & msrc_config_control_bit(check) == 0)
+            }
+            _ => Ok(self.limit_checks.enabled(check)),
+        }
+    }
+
+    // Checks and sets the value of a limit check
+    async fn apply_limit_check_value(
+        &mut self,
+        check: LimitCheck,
+        value: f32,
+    ) -> Result<(), Error<E>> {
+        match check {
+            LimitCheck::SigmaFinalRange => {
+                if !(0.0..=65535.0).contains(&value) {
+                    return Err(Error::InvalidSigmaLimit);
+                }
+            }
+            _ => {
+                // Q9.7 range for the device checks
+                if !(0.0..=511.99).contains(&value) {
+                    return Err(Error::InvalidSignalRateLimit);
+                }
+            }
+        }
+
+        match check {
+            LimitCheck::SignalRateFinalRange if self.limit_checks.enabled(check) => {
+                self.write_16bit(
+                    Register::FINAL_RANGE_CONFIG_MIN_COUNT_RATE_RTN_LIMIT,
+                    encode_q9_7(value),
+                )
+                .await?;
+            }
+            LimitCheck::SignalRateMsrc | LimitCheck::SignalRatePreRange => {
+                self.write_16bit(
+                    Register::PRE_RANGE_MIN_COUNT_RATE_RTN_LIMIT,
+                    encode_q9_7(value),
+                )
+                .await?;
+            }
+            _ => {}
+        }
+        self.limit_checks.values[check as usize] = value;
+
+        Ok(())
+    }
+
+    /// Returns the value of a limit check, in mm for `SigmaFinalRange` and in MCPS for the others
+    pub async fn get_limit_check_value(&mut self, check: LimitCheck) -> Result<f32, E> {
+        match check {
+            LimitCheck::SignalRateMsrc | LimitCheck::SignalRatePreRange => Ok(decode_q9_7(
+                self.read_16bit(Register::PRE_RANGE_MIN_COUNT_RATE_RTN_LIMIT)
+                    .await?,
+            )),
+            _ => Ok(self.limit_checks.value(check)),
+        }
+    }
+
+    // Reads the reference SPAD count and type, and the good SPAD map, from the NVM
+    async fn get_spad_info(&mut self) -> Result<(u8, u8, [u8; 6]), Error<E>> {
+        self.write_byte(0x80, 0x01).await?;
+        self.write_byte(0xFF, 0x01).await?;
+        self.write_byte(0x00, 0x00).await?;
+
+        self.write_byte(0xFF, 0x06).await?;
+        let mut tmp83 = self.read_byte(0x83).await?;
+        self.write_byte(0x83, tmp83 | 0x04).await?;
+
+        self.write_byte(0xFF, 0x07).await?;
+        self.write_byte(0x81, 0x01).await?;
+
+        self.write_byte(0x80, 0x01).await?;
+
+        let tmp = (self.read_nvm(0x6b).await? >> 8) as u8;
+
+        let count: u8 = tmp & 0x7f;
+        let type_is_aperture: u8 = (tmp >> 7) & 0x01;
+
+        let mut good_spad_map = [0; 6];
+        good_spad_map[..4].copy_from_slice(&self.read_nvm(0x24).await?.to_be_bytes());
+        good_spad_map[4..].copy_from_slice(&self.read_nvm(0x25).await?.to_be_bytes()[..2]);
+
+        self.write_byte(0x81, 0x00).await?;
+        self.write_byte(0xFF, 0x06).await?;
+
+        tmp83 = self.read_byte(0x83).await?;
+        self.write_byte(0x83, tmp83 & !0x04).await?;
+
+        self.write_byte(0xFF, 0x01).await?;
+        self.write_byte(0x00, 0x01).await?;
+
+        self.write_byte(0xFF, 0x00).await?;
+        self.write_byte(0x80, 0x00).await?;
+
+        Ok((count, type_is_aperture, good_spad_map))
+    }
+
+    // Reads a 32-bit word of the NVM, once `get_spad_info` gave access to it
+    async fn read_nvm(&mut self, address: u8) -> Result<u32, Error<E>> {
+        self.write_byte(0x94, address).await?;
+        self.write_byte(0x83, 0x00).await?;
+
+        let mut waited_microseconds = 0;
+        while self.read_byte(0x83).await? == 0x00 {
+            self.poll_delay(&mut waited_microseconds).await?;
+        }
+
+        self.write_byte(0x83, 0x01).await?;
+        let mut buffer = [0; 4];
+        self.com
+            .write_read(self.address, &[0x90], &mut buffer)
+            .await?;
+
+        Ok(u32::from_be_bytes(buffer))
+    }
+
+    /// Set new address for device. Returns `Error::InvalidAddress` if the address is out of the
+    /// 0x08 to 0x77 range, which excludes the reserved 7-bit addresses.
+    pub async fn set_device_address(&mut self, address: u8) -> Result<(), Error<E>> {
+        if !(0x08..=0x77).contains(&address) {
+            return Err(Error::InvalidAddress(address));
+        }
+
+        self.write_register(Register::REG_I2C_SLAVE_DEVICE_ADDRESS, address)
+            .await?;
+        self.address = address;
+
+        Ok(())
+    }
+
+    // Reads a measurement if the interrupt status reports one is ready
+    #[maybe_async_cfg::only_if(sync)]
+    fn try_read_measurement(&mut self) -> nb::Result<RangingMeasurement, Error<E>> {
+        match self.read_register(Register::RESULT_INTERRUPT_STATUS).await {
+            Ok(r) => {
+                if r.trailing_zeros() >= 3 {
+                    Err(nb::Error::WouldBlock)
+                } else {
+                    self.read_result()
+                        .await
+                        .map_err(|e| nb::Error::Other(Error::from(e)))
+                }
+            }
+            Err(e) => Err(nb::Error::Other(Error::from(e))),
+        }
+    }
+
+    // Waits for a measurement by polling the interrupt status, then reads it
+    async fn wait_measurement(&mut self) -> Result<RangingMeasurement, Error<E>> {
+        let mut waited_microseconds = 0;
+        while (self
+            .read_register(Register::RESULT_INTERRUPT_STATUS)
+            .await?)
+            .trailing_zeros()
+            >= 3
+        {
+            self.poll_delay(&mut waited_microseconds).await?;
+        }
+
+        Ok(self.read_result().await?)
+    }
+
+    // Waits for a measurement through the GPIO1 interrupt output, then reads it
+    async fn wait_measurement_interrupt<P>(
+        &mut self,
+        gpio1: &mut P,
+    ) -> Result<RangingMeasurement, Error<E>>
+    where
+        P: InputPin,
+    {
+        self.wait_for_interrupt(gpio1).await?;
+        Ok(self.read_result().await?)
+    }
+
+    // Polls GPIO1 until it reaches its active level, sleeping between polls; returns
+    // `Error::Timeout` once the I/O timeout has elapsed
+    #[maybe_async_cfg::only_if(sync)]
+    fn wait_for_interrupt<P>(&mut self, gpio1: &mut P) -> Result<(), Error<E>>
+    where
+        P: InputPin,
+    {
+        let active_high = self.interrupt_polarity == InterruptPolarity::ActiveHigh;
+        let mut waited_microseconds = 0;
+        while gpio1.is_high().map_err(|_| Error::PinError)? != active_high {
+            self.poll_delay(&mut waited_microseconds).await?;
+        }
+
+        Ok(())
+    }
+
+    // Awaits the active level of GPIO1
+    #[maybe_async_cfg::only_if(async)]
+    async fn wait_for_interrupt<P>(&mut self, gpio1: &mut P) -> Result<(), Error<E>>
+    where
+        P: InputPin,
+    {
+        match self.interrupt_polarity {
+            InterruptPolarity::ActiveLow => gpio1.wait_for_low().await,
+            InterruptPolarity::ActiveHigh => gpio1.wait_for_high().await,
+        }
+        .map_err(|_| Error::PinError)
+    }
+
+    // Reads the result block in one transfer and clears the interrupt
+    async fn read_result(&mut self) -> Result<RangingMeasurement, E> {
+        let result = self.read_registers(Register::RESULT_RANGE_STATUS).await;
+        self.write_register(Register::SYSTEM_INTERRUPT_CLEAR, 0x01)
+            .await?;
+
+        let mut measurement = RangingMeasurement::from_result_block(&result?);
+        let xtalk_compensation_rate_mcps = if self.xtalk_compensation_enabled {
+            self.xtalk_compensation_rate_mcps
+        } else {
+            0.0
+        };
+        self.limit_checks.apply(
+            &mut measurement,
+            &self.timeouts,
+            xtalk_compensation_rate_mcps,
+        );
+
+        Ok(measurement)
+    }
+
+    // Sleeps between two polls of the device; returns `Error::Timeout` once the I/O timeout has
+    // elapsed
+    async fn poll_delay(&mut self, waited_microseconds: &mut u32) -> Result<(), Error<E>> {
+        if self.io_timeout_microseconds != 0 && *waited_microseconds >= self.io_timeout_microseconds
+        {
+            return Err(Error::Timeout);
+        }
+        self.delay.delay_us(POLL_INTERVAL_MICROSECONDS).await;
+        *waited_microseconds = waited_microseconds.saturating_add(POLL_INTERVAL_MICROSECONDS);
+
+        Ok(())
+    }
+
+    // Restores the stop variable, then starts ranging with the given SYSRANGE_START value
+    async fn start_ranging(&mut self, sysrange_mode: u8) -> Result<(), E> {
+        self.write_byte(0x80, 0x01).await?;
+        self.write_byte(0xFF, 0x01).await?;
+        self.write_byte(0x00, 0x00).await?;
+        let sv = self.stop_variable;
+        self.write_byte(0x91, sv).await?;
+        self.write_byte(0x00, 0x01).await?;
+        self.write_byte(0xFF, 0x00).await?;
+        self.write_byte(0x80, 0x00).await?;
+
+        self.write_register(Register::SYSRANGE_START, sysrange_mode)
+            .await
+    }
+
+    // Sets the inter-measurement period, then starts timed ranging
+    async fn start_timed_ranging(&mut self, period_millis: u32) -> Result<(), E> {
+        self.set_intermeasurement_period(period_millis).await?;
+        self.start_ranging(Timed::SYSRANGE_MODE).await
+    }
+
+    // Sets the inter-measurement period of timed ranging
+    async fn set_intermeasurement_period(&mut self, period_millis: u32) -> Result<(), E> {
+        let osc_calibrate_value = self.read_16bit(Register::OSC_CALIBRATE_VAL).await?;
+        let mut period = period_millis;
+        if osc_calibrate_value != 0 {
+            period = period.saturating_mul(osc_calibrate_value as u32);
+        }
+        self.write_32bit(Register::SYSTEM_INTERMEASUREMENT_PERIOD, period)
+            .await?;
+        self.intermeasurement_period_millis = period_millis;
+
+        Ok(())
+    }
+
+    // Stops continuous ranging and waits for the measurement in progress
+    async fn stop_ranging(&mut self) -> Result<(), Error<E>> {
+        self.write_register(Register::SYSRANGE_START, 0x01).await?;
+        self.write_byte(0xFF, 0x01).await?;
+        self.write_byte(0x00, 0x00).await?;
+        self.write_byte(0x91, 0x00).await?;
+        self.write_byte(0x00, 0x01).await?;
+        self.write_byte(0xFF, 0x00).await?;
+        self.wait_stop_completed().await
+    }
+
+    // Waits for the measurement in progress when continuous ranging was stopped, then clears its
+    // interrupt
+    async fn wait_stop_completed(&mut self) -> Result<(), Error<E>> {
+        let mut waited_microseconds = 0;
+        loop {
+            self.write_byte(0xFF, 0x01).await?;
+            let stopped = self.read_byte(0x04).await;
+            self.write_byte(0xFF, 0x00).await?;
+            if stopped? == 0 {
+                break;
+            }
+            self.poll_delay(&mut waited_microseconds).await?;
+        }
+
+        self.write_register(Register::SYSTEM_INTERRUPT_CLEAR, 0x01)
+            .await?;
+
+        Ok(())
+    }
+
+    // Performs a single calibration
+    async fn perform_single_ref_calibration(&mut self, vhv_init_byte: u8) -> Result<(), Error<E>> {
+        self.write_register(Register::SYSRANGE_START, 0x01 | vhv_init_byte)
+            .await?;
+        let mut waited_microseconds = 0;
+        while (self
+            .read_register(Register::RESULT_INTERRUPT_STATUS)
+            .await?)
+            .trailing_zeros()
+            >= 3
+        {
+            self.poll_delay(&mut waited_microseconds).await?;
+        }
+
+        self.write_register(Register::SYSTEM_INTERRUPT_CLEAR, 0x01)
+            .await?;
+        self.write_register(Register::SYSRANGE_START, 0x00).await?;
+
+        Ok(())
+    }
+
+    // Without calibration data, the reference SPADs are set up from the factory selection and the
+    // reference calibration is performed
+    async fn init_hardware(&mut self) -> Result<(), Error<E>> {
+        let calibration = self.calibration;
+
+        // enable the sensor, sensor uses 1V8 mode for I/O by default; switch to 2V8 mode if necessary
+        if self.io_mode2v8 {
+            let ext_sup_hv = self
+                .read_register(Register::VHV_CONFIG_PAD_SCL_SDA__EXTSUP_HV)
+                .await?;
+            self.write_register(
+                Register::VHV_CONFIG_PAD_SCL_SDA__EXTSUP_HV,
+                ext_sup_hv | 0x01,
+            )
+            .await?;
+        }
+
+        // set I2C standard mode
+        self.write_byte(0x88, 0x00).await?;
+        self.write_byte(0x80, 0x01).await?;
+        self.write_byte(0xFF, 0x01).await?;
+        self.write_byte(0x00, 0x00).await?;
+        self.stop_variable = self.read_byte(0x91).await?;
+        self.write_byte(0x00, 0x01).await?;
+        self.write_byte(0xFF, 0x00).await?;
+        self.write_byte(0x80, 0x00).await?;
+
+        // disable SIGNAL_RATE_MSRC (bit 1) and SIGNAL_RATE_PRE_RANGE (bit 4) limit checks
+        self.apply_limit_check_enable(LimitCheck::SignalRateMsrc, false)
+            .await?;
+        self.apply_limit_check_enable(LimitCheck::SignalRatePreRange, false)
+            .await?;
+
+        // set final range signal rate limit to 0.25 MCPS (million counts per second)
+        self.apply_signal_rate_limit(0.25).await?;
+
+        self.write_register(Register::SYSTEM_SEQUENCE_CONFIG, 0xFF)
+            .await?;
+
+        // the good SPAD map (RefGoodSpadMap) is read from the NVM like
+        // VL53L0X_get_info_from_device() does in the API; GLOBAL_CONFIG_SPAD_ENABLES_REF_0 through
+        // _5 only hold the enabled SPADs, which are no longer all the good ones when the sensor
+        // kept its power through a reset of the MCU
+        let (spad_count, spad_type_is_aperture, good_spad_map) = self.get_spad_info().await?;
+        self.ref_good_spad_map = good_spad_map;
+
+        if calibration.is_none() {
+            self.apply_reference_spads(ReferenceSpads {
+                count: spad_count,
+                is_aperture: spad_type_is_aperture != 0,
+            })
+            .await?;
+        }
+
+        self.write_byte(0xFF, 0x01).await?;
+        self.write_byte(0x00, 0x00).await?;
+
+        self.write_byte(0xFF, 0x00).await?;
+        self.write_byte(0x09, 0x00).await?;
+        self.write_byte(0x10, 0x00).await?;
+        self.write_byte(0x11, 0x00).await?;
+
+        self.write_byte(0x24, 0x01).await?;
+        self.write_byte(0x25, 0xFF).await?;
+        self.write_byte(0x75, 0x00).await?;
+
+        self.write_byte(0xFF, 0x01).await?;
+        self.write_byte(0x4E, 0x2C).await?;
+        self.write_byte(0x48, 0x00).await?;
+        self.write_byte(0x30, 0x20).await?;
+
+        self.write_byte(0xFF, 0x00).await?;
+        self.write_byte(0x30, 0x09).await?;
+        self.write_byte(0x54, 0x00).await?;
+        self.write_byte(0x31, 0x04).await?;
+        self.write_byte(0x32, 0x03).await?;
+        self.write_byte(0x40, 0x83).await?;
+        self.write_byte(0x46, 0x25).await?;
+        self.write_byte(0x60, 0x00).await?;
+        self.write_byte(0x27, 0x00).await?;
+        self.write_byte(0x50, 0x06).await?;
+        self.write_byte(0x51, 0x00).await?;
+        self.write_byte(0x52, 0x96).await?;
+        self.write_byte(0x56, 0x08).await?;
+        self.write_byte(0x57, 0x30).await?;
+        self.write_byte(0x61, 0x00).await?;
+        self.write_byte(0x62, 0x00).await?;
+        self.write_byte(0x64, 0x00).await?;
+        self.write_byte(0x65, 0x00).await?;
+        self.write_byte(0x66, 0xA0).await?;
+
+        self.write_byte(0xFF, 0x01).await?;
+        self.write_byte(0x22, 0x32).await?;
+        self.write_byte(0x47, 0x14).await?;
+        self.write_byte(0x49, 0xFF).await?;
+        self.write_byte(0x4A, 0x00).await?;
+
+        self.write_byte(0xFF, 0x00).await?;
+        self.write_byte(0x7A, 0x0A).await?;
+        self.write_byte(0x7B, 0x00).await?;
+        self.write_byte(0x78, 0x21).await?;
+
+        self.write_byte(0xFF, 0x01).await?;
+        self.write_byte(0x23, 0x34).await?;
+        self.write_byte(0x42, 0x00).await?;
+        self.write_byte(0x44, 0xFF).await?;
+        self.write_byte(0x45, 0x26).await?;
+        self.write_byte(0x46, 0x05).await?;
+        self.write_byte(0x40, 0x40).await?;
+        self.write_byte(0x0E, 0x06).await?;
+        self.write_byte(0x20, 0x1A).await?;
+        self.write_byte(0x43, 0x40).await?;
+
+        self.write_byte(0xFF, 0x00).await?;
+        self.write_byte(0x34, 0x03).await?;
+        self.write_byte(0x35, 0x44).await?;
+
+        self.write_byte(0xFF, 0x01).await?;
+        self.write_byte(0x31, 0x04).await?;
+        self.write_byte(0x4B, 0x09).await?;
+        self.write_byte(0x4C, 0x05).await?;
+        self.write_byte(0x4D, 0x04).await?;
+
+        self.write_byte(0xFF, 0x00).await?;
+        self.write_byte(0x44, 0x00).await?;
+        self.write_byte(0x45, 0x20).await?;
+        self.write_byte(0x47, 0x08).await?;
+        self.write_byte(0x48, 0x28).await?;
+        self.write_byte(0x67, 0x00).await?;
+        self.write_byte(0x70, 0x04).await?;
+        self.write_byte(0x71, 0x01).await?;
+        self.write_byte(0x72, 0xFE).await?;
+        self.write_byte(0x76, 0x00).await?;
+        self.write_byte(0x77, 0x00).await?;
+
+        self.write_byte(0xFF, 0x01).await?;
+        self.write_byte(0x0D, 0x01).await?;
+
+        self.write_byte(0xFF, 0x00).await?;
+        self.write_byte(0x80, 0x01).await?;
+        self.write_byte(0x01, 0xF8).await?;
+
+        self.write_byte(0xFF, 0x01).await?;
+        self.write_byte(0x8E, 0x01).await?;
+        self.write_byte(0x00, 0x01).await?;
+        self.write_byte(0xFF, 0x00).await?;
+        self.write_byte(0x80, 0x00).await?;
+
+        self.apply_interrupt_polarity(InterruptPolarity::ActiveLow)
+            .await?;
+        self.apply_interrupt_mode(InterruptMode::NewSampleReady)
+            .await?;
+
+        self.measurement_timing_budget_microseconds = self.get_measurement_timing_budget().await?;
+        self.write_register(Register::SYSTEM_SEQUENCE_CONFIG, 0xE8)
+            .await?;
+
+        // recalculate timing budget
+        let mtbm = self.measurement_timing_budget_microseconds;
+        self.apply_measurement_timing_budget(mtbm).await?;
+
+        match calibration {
+            Some(calibration) => {
+                self.apply_calibration_data(&calibration).await?;
+            }
+            None => self.ref_calibration().await?,
+        }
+
+        Ok(())
+    }
+
+    /// Returns WHO_AM_I register
+    pub async fn who_am_i(&mut self) -> Result<u8, E> {
+        self.read_register(Register::WHO_AM_I).await
+    }
+
+    /// Returns the VCSEL (vertical cavity surface emitting laser) pulse period in PCLKs
+    /// for the given period type
+    pub async fn get_vcsel_pulse_period(&mut self, ty: VcselPeriodType) -> Result<u8, E> {
+        match ty {
+            VcselPeriodType::VcselPeriodPreRange => Ok(decode_vcsel_period(
+                self.read_register(Register::PRE_RANGE_CONFIG_VCSEL_PERIOD)
+                    .await?,
+            )),
+            VcselPeriodType::VcselPeriodFinalRange => Ok(decode_vcsel_period(
+                self.read_register(Register::FINAL_RANGE_CONFIG_VCSEL_PERIOD)
+                    .await?,
+            )),
+        }
+    }
+
+    /// Returns the enabled steps of the ranging sequence
+    pub async fn get_sequence_steps(&mut self) -> Result<SeqStepEnables, E> {
+        self.get_sequence_step_enables().await
+    }
+
+    /// Returns the timeouts of the ranging sequence steps, as read from the device
+    pub async fn get_sequence_step_timeouts(&mut self) -> Result<SeqStepTimeouts, E> {
+        let enables = self.get_sequence_step_enables().await?;
+        self.read_sequence_step_timeouts(&enables).await
+    }
+
+    async fn get_sequence_step_enables(&mut self) -> Result<SeqStepEnables, E> {
+        let sequence_config: u8 = self.read_register(Register::SYSTEM_SEQUENCE_CONFIG).await?;
+        Ok(SeqStepEnables {
+            tcc: ((sequence_config >> 4) & 0x1) == 1,
+            dss: ((sequence_config >> 3) & 0x1) == 1,
+            msrc: ((sequence_config >> 2) & 0x1) == 1,
+            pre_range: ((sequence_config >> 6) & 0x1) == 1,
+            final_range: ((sequence_config >> 7) & 0x1) == 1,
+        })
+    }
+
+    async fn read_sequence_step_timeouts(
+        &mut self,
+        enables: &SeqStepEnables,
+    ) -> Result<SeqStepTimeouts, E> {
+        let pre_range_mclks = decode_timeout(
+            self.read_16bit(Register::PRE_RANGE_CONFIG_TIMEOUT_MACROP_HI)
+                .await?,
+        );
+        let mut final_range_mclks = decode_timeout(
+            self.read_16bit(Register::FINAL_RANGE_CONFIG_TIMEOUT_MACROP_HI)
+                .await?,
+        );
+        if enables.pre_range {
+            // the final range register includes the pre-range, unless it was set shorter
+            final_range_mclks = final_range_mclks.saturating_sub(pre_range_mclks);
+        };
+        let pre_range_vcselperiod_pclks = self
+            .get_vcsel_pulse_period(VcselPeriodType::VcselPeriodPreRange)
+            .await?;
+        let msrc_dss_tcc_mclks = self
+            .read_register(Register::MSRC_CONFIG_TIMEOUT_MACROP)
+            .await? as u16
+            + 1;
+        let final_range_vcsel_period_pclks = self
+            .get_vcsel_pulse_period(VcselPeriodType::VcselPeriodFinalRange)
+            .await?;
+
+        Ok(SeqStepTimeouts {
+            pre_range_vcselperiod_pclks,
+            msrc_dss_tcc_mclks,
+            msrc_dss_tcc_microseconds: timeout_mclks_to_microseconds(
+                msrc_dss_tcc_mclks,
+                pre_range_vcselperiod_pclks,
+            ),
+            pre_range_mclks,
+            pre_range_microseconds: timeout_mclks_to_microseconds(
+                pre_range_mclks,
+                pre_range_vcselperiod_pclks,
+            ),
+            final_range_mclks,
+            final_range_vcsel_period_pclks,
+            final_range_microseconds: timeout_mclks_to_microseconds(
+                final_range_mclks,
+                final_range_vcsel_period_pclks,
+            ),
+        })
+    }
+
+    /// Returns the measurement timing budget in microseconds, as computed from the sequence step
+    /// timeouts read from the device
+    pub async fn measurement_timing_budget(&mut self) -> Result<u32, E> {
+        self.get_measurement_timing_budget().await
+    }
+
+    async fn get_measurement_timing_budget(&mut self) -> Result<u32, E> {
+        let start_overhead: u32 = 1910;
+        let end_overhead: u32 = 960;
+        let msrc_overhead: u32 = 660;
+        let tcc_overhead: u32 = 590;
+        let dss_overhead: u32 = 690;
+        let pre_range_overhead: u32 = 660;
+        let final_range_overhead: u32 = 550;
+
+        let enables = self.get_sequence_step_enables().await?;
+        let timeouts = self.read_sequence_step_timeouts(&enables).await?;
+
+        // start and end overhead times always present
+        let mut budget_microseconds = start_overhead + end_overhead;
+        if enables.tcc {
+            budget_microseconds += timeouts.msrc_dss_tcc_microseconds + tcc_overhead;
+        }
+        if enables.dss {
+            budget_microseconds += 2 * (timeouts.msrc_dss_tcc_microseconds + dss_overhead);
+        } else if enables.msrc {
+            budget_microseconds += timeouts.msrc_dss_tcc_microseconds + msrc_overhead;
+        }
+        if enables.pre_range {
+            budget_microseconds += timeouts.pre_range_microseconds + pre_range_overhead;
+        }
+        if enables.final_range {
+            budget_microseconds += timeouts.final_range_microseconds + final_range_overhead;
+        }
+
+        Ok(budget_microseconds)
+    }
+
+    // Splits the timing budget among the sequence steps and writes the final range timeout
+    async fn apply_measurement_timing_budget(
+        &mut self,
+        budget_microseconds: u32,
+    ) -> Result<(), Error<E>> {
+        let enables = self.get_sequence_step_enables().await?;
+        let timeouts = self.read_sequence_step_timeouts(&enables).await?;
+        let final_range_timeout_mclks =
+            final_range_timeout_mclks(&enables, &timeouts, budget_microseconds)?;
+
+        self.write_16bit(
+            Register::FINAL_RANGE_CONFIG_TIMEOUT_MACROP_HI,
+            encode_timeout(final_range_timeout_mclks),
+        )
+        .await?;
+
+        self.measurement_timing_budget_microseconds = budget_microseconds;
+        self.timeouts = self.read_sequence_step_timeouts(&enables).await?;
+
+        Ok(())
+    }
+}
+
+#[maybe_async_cfg::maybe(
+    sync(keep_self),
+    async(feature = "async"),
+    idents(
+        I2c(sync, async = "AsyncI2c"),
+        DelayNs(sync, async = "AsyncDelayNs"),
+        InputPin(sync, async = "Wait"),
+        VL53L0X(sync, async = "VL53L0XAsync")
+    )
+)]
+impl<I2C, D, XSHUT, E> VL53L0X<I2C, D, Idle, XSHUT>
+where
+    I2C: I2c<SevenBitAddress, Error = E>,
+    D: DelayNs,
+{
+    /// Starts continuous back-to-back ranging: as soon as a measurement is finished, another one
+    /// is started without delay, until `stop_continuous` is called
+    ///
+    /// On error the idle driver is returned along with the error.
+    pub async fn start_continuous(
+        mut self,
+    ) -> Result<VL53L0X<I2C, D, Continuous, XSHUT>, (VL53L0X<I2C, D, Idle, XSHUT>, Error<E>)> {
+        match self.start_ranging(Continuous::SYSRANGE_MODE).await {
+            Ok(()) => Ok(self.into_mode()),
+            Err(e) => Err((self, Error::from(e))),
+        }
+    }
+
+    /// Starts continuous timed ranging: the sensor takes a measurement every `period_millis`
+    /// milliseconds, until `stop_continuous` is called
+    ///
+    /// On error the idle driver is returned along with the error.
+    pub async fn start_timed(
+        mut self,
+        period_millis: u32,
+    ) -> Result<VL53L0X<I2C, D, Timed, XSHUT>, (VL53L0X<I2C, D, Idle, XSHUT>, Error<E>)> {
+        match self.start_timed_ranging(period_millis).await {
+            Ok(()) => Ok(self.into_mode()),
+            Err(e) => Err((self, Error::from(e))),
+        }
+    }
+
+    /// Starts a single measurement and returns without waiting for it; the measurement is then
+    /// read with `poll_single`, or awaited with `wait_single` on the async driver
+    pub async fn start_single(&mut self) -> Result<(), Error<E>> {
+        self.start_ranging(0x01).await?;
+
+        Ok(())
+    }
+
+    /// Returns the measurement started by `start_single`, or `nb::Error::WouldBlock` while it is
+    /// not ready yet
+    #[maybe_async_cfg::only_if(sync)]
+    pub fn poll_single(&mut self) -> nb::Result<RangingMeasurement, Error<E>> {
+        self.try_read_measurement()
+    }
+
+    /// Waits for the measurement started by `start_single` and returns it, or `Error::Timeout`
+    /// once the I/O timeout has elapsed
+    #[maybe_async_cfg::only_if(async)]
+    pub async fn wait_single(&mut self) -> Result<RangingMeasurement, Error<E>> {
+        self.wait_measurement().await
+    }
+
+    async fn start_single_ranging(&mut self) -> Result<(), Error<E>> {
+        self.start_single().await?;
+
+        // wait until start bit has been cleared
+        let mut waited_microseconds = 0;
+        while (self.read_register(Register::SYSRANGE_START).await? & 0x01) != 0 {
+            self.poll_delay(&mut waited_microseconds).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Returns a single reading in millimeters
+    pub async fn read_range_single_millimeters_blocking(&mut self) -> Result<u16, Error<E>> {
+        Ok(self.read_measurement_single_blocking().await?.range_mm)
+    }
+
+    /// Returns a single full ranging measurement
+    pub async fn read_measurement_single_blocking(
+        &mut self,
+    ) -> Result<RangingMeasurement, Error<E>> {
+        self.start_single_ranging().await?;
+        self.wait_measurement().await
+    }
+
+    /// Returns a single reading in millimeters, waiting for the sensor to assert its GPIO1
+    /// interrupt output once the measurement is done
+    ///
+    /// GPIO1 is configured as an active low "new sample ready" output by default, see
+    /// `set_interrupt_mode` and `set_interrupt_polarity`. The blocking driver polls the pin level,
+    /// sleeping between polls, and returns `Error::Timeout` once the I/O timeout has elapsed; the
+    /// async driver awaits the active level.
+    pub async fn read_range_single_millimeters_interrupt<P>(
+        &mut self,
+        gpio1: &mut P,
+    ) -> Result<u16, Error<E>>
+    where
+        P: InputPin,
+    {
+        Ok(self
+            .read_measurement_single_interrupt(gpio1)
+            .await?
+            .range_mm)
+    }
+
+    /// Returns a single full ranging measurement, waiting for the sensor to assert its GPIO1
+    /// interrupt output once the measurement is done
+    pub async fn read_measurement_single_interrupt<P>(
+        &mut self,
+        gpio1: &mut P,
+    ) -> Result<RangingMeasurement, Error<E>>
+    where
+        P: InputPin,
+    {
+        self.start_single_ranging().await?;
+        self.wait_measurement_interrupt(gpio1).await
+    }
+
+    /// Sets the return signal rate limit of the final range step, in MCPS (mega counts per
+    /// second), and enables the limit check. A lower limit increases the potential range, and the
+    /// likelihood of inaccurate readings. Defaults to 0.25 MCPS. Returns
+    /// `Error::InvalidSignalRateLimit` if the limit is out of the 0 to 511.99 MCPS range.
+    pub async fn set_signal_rate_limit(&mut self, limit: f32) -> Result<(), Error<E>> {
+        self.apply_signal_rate_limit(limit).await
+    }
+
+    /// Enables or disables a limit check
+    pub async fn set_limit_check_enable(
+        &mut self,
+        check: LimitCheck,
+        enable: bool,
+    ) -> Result<(), Error<E>> {
+        self.apply_limit_check_enable(check, enable).await
+    }
+
+    /// Sets the value of a limit check, in mm for `SigmaFinalRange` and in MCPS (million counts
+    /// per second) for the others. Returns `Error::InvalidSigmaLimit` or
+    /// `Error::InvalidSignalRateLimit` if the value is out of range.
+    ///
+    /// The value of a disabled check is kept and used once the check is enabled.
+    pub async fn set_limit_check_value(
+        &mut self,
+        check: LimitCheck,
+        value: f32,
+    ) -> Result<(), Error<E>> {
+        self.apply_limit_check_value(check, value).await
+    }
+
+    /// Set the measurement timing budget in microseconds, which is the time allowed for one measurement;
+    /// the ST API and this library take care of splitting the timing budget among the sub-steps in the
+    /// ranging sequence. A longer timing budget allows for more accurate measurements. Increasing the
+    /// budget by a factor of N decreases the range measurement standard deviation by a factor of sqrt(N).
+    /// Defaults to about 33 milliseconds; the minimum is 20 ms, or more if the enabled sequence steps
+    /// take longer, otherwise `Error::InvalidTimingBudget` is returned. The maximum, a few seconds,
+    /// is set by the final range timeout register and depends on the VCSEL pulse periods.
+    pub async fn set_measurement_timing_budget(
+        &mut self,
+        budget_microseconds: u32,
+    ) -> Result<(), Error<E>> {
+        self.apply_measurement_timing_budget(budget_microseconds)
+            .await
+    }
+
+    /// Set the VCSEL (vertical cavity surface emitting laser) pulse period for the given period
+    /// type to the given value in PCLKs. Longer periods increase the potential range of the sensor.
+    ///
+    /// Valid values are (even numbers only): 12 to 18 for the pre-range, 8 to 14 for the final
+    /// range; `Error::InvalidVcselPeriod` is returned for any other value. The phase check limits and the step
+    /// timeouts are updated for the new period, the timing budget is applied again and the phase
+    /// calibration is performed again, as required after a period change.
+    ///
+    /// Returns `Error::InvalidTimingBudget`, or `Error::InvalidSequenceStepTimeout` if the
+    /// pre-range timeout cannot be expressed with the new period, leaving the period unchanged,
+    /// if the current timing budget does not fit the new period.
+    pub async fn set_vcsel_pulse_period(
+        &mut self,
+        ty: VcselPeriodType,
+        period_pclks: u8,
+    ) -> Result<(), Error<E>> {
+        let enables = self.get_sequence_step_enables().await?;
+        let timeouts = self.read_sequence_step_timeouts(&enables).await?;
+        let budget_microseconds = self.measurement_timing_budget_microseconds;
+
+        // the timeouts with the new period are checked against the timing budget before any
+        // register is written
+        let mut new_timeouts = timeouts;
+        let result = match ty {
+            VcselPeriodType::VcselPeriodPreRange => {
+                // phase check limits
+                let phase_high = match period_pclks {
+                    12 => 0x18,
+                    14 => 0x30,
+                    16 => 0x40,
+                    18 => 0x50,
+                    _ => return Err(Error::InvalidVcselPeriod(period_pclks)),
+                };
+
+                let pre_range_mclks =
+                    timeout_microseconds_to_mclks(timeouts.pre_range_microseconds, period_pclks);
+                if pre_range_mclks > u16::MAX as u32 {
+                    return Err(Error::InvalidSequenceStepTimeout);
+                }
+                // the MSRC register holds the timeout minus 1, up to 255
+                let msrc_dss_tcc_mclks =
+                    timeout_microseconds_to_mclks(timeouts.msrc_dss_tcc_microseconds, period_pclks)
+                        .clamp(1, 256) as u16;
+                new_timeouts.pre_range_vcselperiod_pclks = period_pclks;
+                new_timeouts.pre_range_mclks = pre_range_mclks as u16;
+                new_timeouts.pre_range_microseconds =
+                    timeout_mclks_to_microseconds(pre_range_mclks as u16, period_pclks);
+                new_timeouts.msrc_dss_tcc_mclks = msrc_dss_tcc_mclks;
+                new_timeouts.msrc_dss_tcc_microseconds =
+                    timeout_mclks_to_microseconds(msrc_dss_tcc_mclks, period_pclks);
+                let final_range_timeout_mclks =
+                    final_range_timeout_mclks(&enables, &new_timeouts, budget_microseconds)?;
+
+                self.write_pre_range_vcsel_period(
+                    period_pclks,
+                    phase_high,
+                    &new_timeouts,
+                    final_range_timeout_mclks,
+                )
+                .await
+            }
+            VcselPeriodType::VcselPeriodFinalRange => {
+                // phase check limits, VCSEL width and phase calibration settings
+                let (phase_high, vcsel_width, phasecal_timeout, phasecal_lim) = match period_pclks {
+                    8 => (0x10, 0x02, 0x0C, 0x30),
+                    10 => (0x28, 0x03, 0x09, 0x20),
+                    12 => (0x38, 0x03, 0x08, 0x20),
+                    14 => (0x48, 0x03, 0x07, 0x20),
+                    _ => return Err(Error::InvalidVcselPeriod(period_pclks)),
+                };
+
+                new_timeouts.final_range_vcsel_period_pclks = period_pclks;
+                let final_range_timeout_mclks =
+                    final_range_timeout_mclks(&enables, &new_timeouts, budget_microseconds)?;
+
+                self.write_final_range_vcsel_period(
+                    period_pclks,
+                    (phase_high, vcsel_width, phasecal_timeout, phasecal_lim),
+                    final_range_timeout_mclks,
+                )
+                .await
+            }
+        };
+
+        // the phase calibration is needed after changing a VCSEL period, even if a write failed
+        // after the period was applied
+        let sequence_config = self.read_register(Register::SYSTEM_SEQUENCE_CONFIG).await?;
+        self.write_register(Register::SYSTEM_SEQUENCE_CONFIG, 0x02)
+            .await?;
+        let calibration = self.perform_single_ref_calibration(0x00).await;
+        self.write_register(Register::SYSTEM_SEQUENCE_CONFIG, sequence_config)
+            .await?;
+        result?;
+        calibration?;
+
+        self.timeouts = self.read_sequence_step_timeouts(&enables).await?;
+
+        Ok(())
+    }
+
+    // Writes the pre-range phase check limits, VCSEL period and timeouts
+    async fn write_pre_range_vcsel_period(
+        &mut self,
+        period_pclks: u8,
+        phase_high: u8,
+        timeouts: &SeqStepTimeouts,
+        final_range_timeout_mclks: u16,
+    ) -> Result<(), E> {
+        self.write_register(Register::PRE_RANGE_CONFIG_VALID_PHASE_HIGH, phase_high)
+            .await?;
+        self.write_register(Register::PRE_RANGE_CONFIG_VALID_PHASE_LOW, 0x08)
+            .await?;
+
+        // apply new VCSEL period
+        self.write_register(
+            Register::PRE_RANGE_CONFIG_VCSEL_PERIOD,
+            encode_vcsel_period(period_pclks),
+        )
+        .await?;
+
+        // update timeouts; the final range register holds the pre-range timeout as well
+        self.write_16bit(
+            Register::PRE_RANGE_CONFIG_TIMEOUT_MACROP_HI,
+            encode_timeout(timeouts.pre_range_mclks),
+        )
+        .await?;
+        self.write_register(
+            Register::MSRC_CONFIG_TIMEOUT_MACROP,
+            (timeouts.msrc_dss_tcc_mclks - 1) as u8,
+        )
+        .await?;
+        self.write_16bit(
+            Register::FINAL_RANGE_CONFIG_TIMEOUT_MACROP_HI,
+            encode_timeout(final_range_timeout_mclks),
+        )
+        .await
+    }
+
+    // Writes the final range phase check limits, VCSEL width, phase calibration settings, VCSEL
+    // period and timeout
+    async fn write_final_range_vcsel_period(
+        &mut self,
+        period_pclks: u8,
+        (phase_high, vcsel_width, phasecal_timeout, phasecal_lim): (u8, u8, u8, u8),
+        final_range_timeout_mclks: u16,
+    ) -> Result<(), E> {
+        self.write_register(Register::FINAL_RANGE_CONFIG_VALID_PHASE_HIGH, phase_high)
+            .await?;
+        self.write_register(Register::FINAL_RANGE_CONFIG_VALID_PHASE_LOW, 0x08)
+            .await?;
+        self.write_register(Register::GLOBAL_CONFIG_VCSEL_WIDTH, vcsel_width)
+            .await?;
+        self.write_register(Register::ALGO_PHASECAL_CONFIG_TIMEOUT, phasecal_timeout)
+            .await?;
+        // ALGO_PHASECAL_LIM, 0x30 on register page 1
+        self.write_byte(0xFF, 0x01).await?;
+        self.write_byte(0x30, phasecal_lim).await?;
+        self.write_byte(0xFF, 0x00).await?;
+
+        // apply new VCSEL period
+        self.write_register(
+            Register::FINAL_RANGE_CONFIG_VCSEL_PERIOD,
+            encode_vcsel_period(period_pclks),
+        )
+        .await?;
+
+        // update timeout, pre-range included
+        self.write_16bit(
+            Register::FINAL_RANGE_CONFIG_TIMEOUT_MACROP_HI,
+            encode_timeout(final_range_timeout_mclks),
+        )
+        .await
+    }
+
+    /// Enables or disables a step of the ranging sequence, then applies the measurement timing
+    /// budget again so that it still holds with the new sequence. Returns
+    /// `Error::InvalidTimingBudget`, leaving the sequence unchanged, if the timing budget is too
+    /// short for the new sequence.
+    ///
+    /// Disabling the TCC, DSS or MSRC steps leaves more of the budget to the final range step.
+    pub async fn set_sequence_step(
+        &mut self,
+        step: SequenceStep,
+        enabled: bool,
+    ) -> Result<(), Error<E>> {
+        let sequence_config = self.read_register(Register::SYSTEM_SEQUENCE_CONFIG).await?;
+        let new_sequence_config = if enabled {
+            sequence_config | step.sequence_config_bit()
+        } else {
+            sequence_config & !step.sequence_config_bit()
+        };
+        if new_sequence_config == sequence_config {
+            return Ok(());
+        }
+
+        self.write_register(Register::SYSTEM_SEQUENCE_CONFIG, new_sequence_config)
+            .await?;
+        let mtbm = self.measurement_timing_budget_microseconds;
+        if let Err(e) = self.set_measurement_timing_budget(mtbm).await {
+            self.write_register(Register::SYSTEM_SEQUENCE_CONFIG, sequence_config)
+                .await?;
+            return Err(e);
+        }
+
+        Ok(())
+    }
+
+    /// Sets the timeout of a ranging sequence step in microseconds, then applies the measurement
+    /// timing budget again, which gives the final range step the remaining time. The TCC, DSS
+    /// and MSRC steps share their timeout. Returns `Error::InvalidSequenceStepTimeout` for the final
+    /// range step, whose timeout is set by the timing budget, and `Error::InvalidTimingBudget`,
+    /// leaving the timeout unchanged, if the timing budget is too short for the new timeout.
+    pub async fn set_sequence_step_timeout(
+        &mut self,
+        step: SequenceStep,
+        timeout_microseconds: u32,
+    ) -> Result<(), Error<E>> {
+        let pre_range_vcsel_period_pclks = self
+            .get_vcsel_pulse_period(VcselPeriodType::VcselPeriodPreRange)
+            .await?;
+        let timeout_mclks =
+            timeout_microseconds_to_mclks(timeout_microseconds, pre_range_vcsel_period_pclks);
+
+        match step {
+            SequenceStep::Tcc | SequenceStep::Dss | SequenceStep::Msrc => {
+                // the register holds the timeout minus 1, up to 255
+                let msrc_timeout = timeout_mclks.clamp(1, 256) - 1;
+                let previous = self
+                    .read_register(Register::MSRC_CONFIG_TIMEOUT_MACROP)
+                    .await?;
+                self.write_register(Register::MSRC_CONFIG_TIMEOUT_MACROP, msrc_timeout as u8)
+                    .await?;
+
+                let mtbm = self.measurement_timing_budget_microseconds;
+                if let Err(e) = self.set_measurement_timing_budget(mtbm).await {
+                    self.write_register(Register::MSRC_CONFIG_TIMEOUT_MACROP, previous)
+                        .await?;
+                    return Err(e);
+                }
+            }
+            SequenceStep::PreRange => {
+                if timeout_mclks > u16::MAX as u32 {
+                    return Err(Error::InvalidSequenceStepTimeout);
+                }
+                let previous = self
+                    .read_16bit(Register::PRE_RANGE_CONFIG_TIMEOUT_MACROP_HI)
+                    .await?;
+                self.write_16bit(
+                    Register::PRE_RANGE_CONFIG_TIMEOUT_MACROP_HI,
+                    encode_timeout(timeout_mclks as u16),
+                )
+                .await?;
+
+                let mtbm = self.measurement_timing_budget_microseconds;
+                if let Err(e) = self.set_measurement_timing_budget(mtbm).await {
+                    self.write_16bit(Register::PRE_RANGE_CONFIG_TIMEOUT_MACROP_HI, previous)
+                        .await?;
+                    return Err(e);
+                }
+            }
+            SequenceStep::FinalRange => return Err(Error::InvalidSequenceStepTimeout),
+        }
+
+        Ok(())
+    }
+
+    /// Applies a ranging profile: signal rate limit, sigma limit, VCSEL pulse periods and
+    /// measurement timing budget are set together, following the ST API ranging examples
+    pub async fn apply_profile(&mut self, profile: RangingProfile) -> Result<(), Error<E>> {
+        let (pre_range_pclks, final_range_pclks) = profile.vcsel_pulse_periods();
+        self.set_vcsel_pulse_period(VcselPeriodType::VcselPeriodPreRange, pre_range_pclks)
+            .await?;
+        self.set_vcsel_pulse_period(VcselPeriodType::VcselPeriodFinalRange, final_range_pclks)
+            .await?;
+        self.set_measurement_timing_budget(profile.measurement_timing_budget_microseconds())
+            .await?;
+        self.set_signal_rate_limit(profile.signal_rate_limit_mcps())
+            .await?;
+        self.set_limit_check_value(LimitCheck::SigmaFinalRange, profile.sigma_limit_mm())
+            .await?;
+        self.set_limit_check_enable(LimitCheck::SigmaFinalRange, true)
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[maybe_async_cfg::maybe(
+    sync(keep_self),
+    async(feature = "async"),
+    idents(
+        I2c(sync, async = "AsyncI2c"),
+        DelayNs(sync, async = "AsyncDelayNs"),
+        InputPin(sync, async = "Wait"),
+        VL53L0X(sync, async = "VL53L0XAsync")
+    )
+)]
+impl<I2C, D, MODE, XSHUT, E> VL53L0X<I2C, D, MODE, XSHUT>
+where
+    I2C: I2c<SevenBitAddress, Error = E>,
+    D: DelayNs,
+    MODE: Ranging,
+{
+    /// Stops continuous ranging, returning to SW standby once the measurement in progress is
+    /// completed
+    ///
+    /// On error the driver is returned in its current mode along with the error, so that stopping
+    /// can be tried again or the sensor power cycled with `hardware_reset`.
+    pub async fn stop_continuous(
+        mut self,
+    ) -> Result<VL53L0X<I2C, D, Idle, XSHUT>, (VL53L0X<I2C, D, MODE, XSHUT>, Error<E>)> {
+        match self.stop_ranging().await {
+            Ok(()) => Ok(self.into_mode()),
+            Err(e) => Err((self, e)),
+        }
+    }
+
+    // Starts ranging again in the mode of the driver, e.g. after a calibration or a power cycle
+    async fn restart_ranging(&mut self) -> Result<(), E> {
+        // the period is lost by a power cycle, and ignored by back-to-back ranging
+        let period_millis = self.intermeasurement_period_millis;
+        self.set_intermeasurement_period(period_millis).await?;
+        self.start_ranging(MODE::SYSRANGE_MODE).await
+    }
+
+    /// Reads and returns range measurement in millimiters
+    #[maybe_async_cfg::only_if(sync)]
+    pub fn read_range_mm(&mut self) -> nb::Result<u16, Error<E>> {
+        self.read_measurement().map(|m| m.range_mm)
+    }
+
+    /// Reads and returns the full ranging measurement
+    #[maybe_async_cfg::only_if(sync)]
+    pub fn read_measurement(&mut self) -> nb::Result<RangingMeasurement, Error<E>> {
+        self.try_read_measurement()
+    }
+
+    /// Returns a range reading in millimeters
+    pub async fn read_range_continuous_millimeters_blocking(&mut self) -> Result<u16, Error<E>> {
+        Ok(self.read_measurement_continuous_blocking().await?.range_mm)
+    }
+
+    /// Returns a full ranging measurement
+    pub async fn read_measurement_continuous_blocking(
+        &mut self,
+    ) -> Result<RangingMeasurement, Error<E>> {
+        self.wait_measurement().await
+    }
+
+    /// Returns a range reading in millimeters, waiting for the sensor to assert its GPIO1
+    /// interrupt output instead of polling the interrupt status register
+    ///
+    /// See `read_range_single_millimeters_interrupt` for the GPIO1 configuration.
+    pub async fn read_range_continuous_millimeters_interrupt<P>(
+        &mut self,
+        gpio1: &mut P,
+    ) -> Result<u16, Error<E>>
+    where
+        P: InputPin,
+    {
+        Ok(self
+            .read_measurement_continuous_interrupt(gpio1)
+            .await?
+            .range_mm)
+    }
+
+    /// Returns a full ranging measurement, waiting for the sensor to assert its GPIO1 interrupt
+    /// output
+    pub async fn read_measurement_continuous_interrupt<P>(
+        &mut self,
+        gpio1: &mut P,
+    ) -> Result<RangingMeasurement, Error<E>>
+    where
+        P: InputPin,
+    {
+        self.wait_measurement_interrupt(gpio1).await
+    }
+}
+
+const DEFAULT_IO_TIMEOUT_MICROSECONDS: u32 = 500_000;
+const POLL_INTERVAL_MICROSECONDS: u32 = 1_000;
+
+// MSRC_CONFIG_CONTROL bit disabling a device limit check
+fn msrc_config_control_bit(check: LimitCheck) -> u8 {
+    match check {
+        LimitCheck::SignalRateMsrc => 0x02,
+        _ => 0x10,
+    }
+}
@@ -0,0 +1,251 @@
+//! Several sensors on one bus
+
+use ehal::delay::DelayNs;
+use ehal::digital::OutputPin;
+use ehal::i2c::{I2c, SevenBitAddress};
+#[cfg(feature = "async")]
+use embedded_hal_async::{delay::DelayNs as AsyncDelayNs, i2c::I2c as AsyncI2c};
+
+#[cfg(feature = "async")]
+use super::VL53L0XAsync;
+use super::VL53L0X;
+use crate::{Continuous, Error, Idle, Ranging, Timed, ADDRESS_DEFAULT};
+
+#[maybe_async_cfg::maybe(
+    sync(keep_self),
+    async(feature = "async", keep_self),
+    idents(
+        VL53L0X(sync, async = "VL53L0XAsync"),
+        SensorArray(sync, async = "SensorArrayAsync")
+    )
+)]
+/// Sensors sharing one bus, each with its own XSHUT pin and address
+///
+/// All sensors boot at the default address 0x29, so they are held in reset through XSHUT and
+/// brought up one at a time, each being moved to its address before the next one boots.
+///
+/// Like the driver, the array tracks the ranging mode of its sensors in its type: it is created
+/// [`Idle`], and `start_continuous`, `start_timed` and `stop_continuous` move all the sensors
+/// from one mode to another at once.
+#[derive(Debug)]
+pub struct SensorArray<I2C, D, XSHUT, const N: usize, MODE = Idle> {
+    sensors: [VL53L0X<I2C, D, MODE, XSHUT>; N],
+}
+
+#[maybe_async_cfg::maybe(
+    sync(keep_self),
+    async(feature = "async"),
+    idents(
+        I2c(sync, async = "AsyncI2c"),
+        DelayNs(sync, async = "AsyncDelayNs"),
+        VL53L0X(sync, async = "VL53L0XAsync"),
+        SensorArray(sync, async = "SensorArrayAsync")
+    )
+)]
+impl<I2C, D, XSHUT, MODE, E, const N: usize> SensorArray<I2C, D, XSHUT, N, MODE>
+where
+    I2C: I2c<SevenBitAddress, Error = E>,
+    D: DelayNs + Clone,
+    XSHUT: OutputPin,
+{
+    /// Returns the sensor at `index`, in bring up order
+    pub fn sensor(&mut self, index: usize) -> Option<&mut VL53L0X<I2C, D, MODE, XSHUT>> {
+        self.sensors.get_mut(index)
+    }
+
+    /// Returns all the sensors, in bring up order
+    pub fn sensors(&mut self) -> &mut [VL53L0X<I2C, D, MODE, XSHUT>; N] {
+        &mut self.sensors
+    }
+
+    /// Returns the sensors
+    pub fn release(self) -> [VL53L0X<I2C, D, MODE, XSHUT>; N] {
+        self.sensors
+    }
+}
+
+#[maybe_async_cfg::maybe(
+    sync(keep_self),
+    async(feature = "async"),
+    idents(
+        I2c(sync, async = "AsyncI2c"),
+        DelayNs(sync, async = "AsyncDelayNs"),
+        VL53L0X(sync, async = "VL53L0XAsync"),
+        SensorArray(sync, async = "SensorArrayAsync")
+    )
+)]
+impl<I2C, D, XSHUT, E, const N: usize> SensorArray<I2C, D, XSHUT, N, Idle>
+where
+    I2C: I2c<SevenBitAddress, Error = E>,
+    D: DelayNs + Clone,
+    XSHUT: OutputPin,
+{
+    /// Brings up the sensors, given as (bus, XSHUT pin, address) in bring up order. Each bus must
+    /// be a handle to the shared bus, e.g. an embedded-hal-bus `RefCellDevice`.
+    ///
+    /// Addresses must be distinct and differ from the default address 0x29, which a sensor takes
+    /// again when it is reset; `Error::InvalidAddress` is returned otherwise.
+    ///
+    /// On error the sensors are returned along with the error, so that their buses and XSHUT
+    /// pins can be taken back with `into_parts`.
+    pub async fn new(
+        sensors: [(I2C, XSHUT, u8); N],
+        delay: D,
+        io_mode2v8: bool,
+    ) -> Result<SensorArray<I2C, D, XSHUT, N, Idle>, ([VL53L0X<I2C, D, Idle, XSHUT>; N], Error<E>)>
+    {
+        let mut sensors = sensors.map(|(i2c, xshut, address)| {
+            VL53L0X::from_parts(i2c, delay.clone(), xshut, address, io_mode2v8, None)
+        });
+
+        match Self::bring_up(&mut sensors, delay).await {
+            Ok(()) => Ok(SensorArray { sensors }),
+            Err(e) => Err((sensors, e)),
+        }
+    }
+
+    // Checks the addresses, then holds all the sensors in reset and boots them one at a time
+    async fn bring_up(
+        sensors: &mut [VL53L0X<I2C, D, Idle, XSHUT>; N],
+        mut delay: D,
+    ) -> Result<(), Error<E>> {
+        for (i, sensor) in sensors.iter().enumerate() {
+            let address = sensor.address;
+            if address == ADDRESS_DEFAULT
+                || sensors[..i].iter().any(|other| other.address == address)
+            {
+                return Err(Error::InvalidAddress(address));
+            }
+        }
+
+        for sensor in sensors.iter_mut() {
+            sensor.power_down().await?;
+        }
+        // let the sensors shut down before booting them
+        delay.delay_us(1_000).await;
+
+        for sensor in sensors.iter_mut() {
+            sensor.power_up().await?;
+        }
+
+        Ok(())
+    }
+
+    /// Power cycles the sensor at `index` and moves it back to its address, without disturbing
+    /// the others, e.g. when it stops answering. Its configuration is back to its default.
+    ///
+    /// Panics if `index` is out of range.
+    pub async fn recover(&mut self, index: usize) -> Result<(), Error<E>> {
+        self.sensors[index].hardware_reset().await
+    }
+
+    /// Starts continuous back-to-back ranging on every sensor, in bring up order
+    ///
+    /// On error the sensors already started are stopped again, and the idle array is returned
+    /// along with the error.
+    pub async fn start_continuous(
+        self,
+    ) -> Result<
+        SensorArray<I2C, D, XSHUT, N, Continuous>,
+        (SensorArray<I2C, D, XSHUT, N, Idle>, Error<E>),
+    > {
+        self.start_all().await
+    }
+
+    /// Starts continuous timed ranging on every sensor, in bring up order, each taking a
+    /// measurement every `period_millis` milliseconds
+    ///
+    /// On error the sensors already started are stopped again, and the idle array is returned
+    /// along with the error.
+    pub async fn start_timed(
+        mut self,
+        period_millis: u32,
+    ) -> Result<SensorArray<I2C, D, XSHUT, N, Timed>, (SensorArray<I2C, D, XSHUT, N, Idle>, Error<E>)>
+    {
+        for index in 0..N {
+            if let Err(e) = self.sensors[index]
+                .set_intermeasurement_period(period_millis)
+                .await
+            {
+                return Err((self, Error::from(e)));
+            }
+        }
+
+        self.start_all().await
+    }
+
+    // Starts ranging in mode `M` on every sensor. A sensor failing to stop once another one failed
+    // to start is power cycled instead.
+    async fn start_all<M: Ranging>(
+        mut self,
+    ) -> Result<SensorArray<I2C, D, XSHUT, N, M>, (SensorArray<I2C, D, XSHUT, N, Idle>, Error<E>)>
+    {
+        for index in 0..N {
+            if let Err(e) = self.sensors[index].start_ranging(M::SYSRANGE_MODE).await {
+                for started in self.sensors[..index].iter_mut() {
+                    if started.stop_ranging().await.is_err() {
+                        started.power_cycle().await.ok();
+                    }
+                }
+                return Err((self, Error::from(e)));
+            }
+        }
+
+        Ok(SensorArray {
+            sensors: self.sensors.map(VL53L0X::into_mode),
+        })
+    }
+}
+
+#[maybe_async_cfg::maybe(
+    sync(keep_self),
+    async(feature = "async"),
+    idents(
+        I2c(sync, async = "AsyncI2c"),
+        DelayNs(sync, async = "AsyncDelayNs"),
+        VL53L0X(sync, async = "VL53L0XAsync"),
+        SensorArray(sync, async = "SensorArrayAsync")
+    )
+)]
+impl<I2C, D, XSHUT, MODE, E, const N: usize> SensorArray<I2C, D, XSHUT, N, MODE>
+where
+    I2C: I2c<SevenBitAddress, Error = E>,
+    D: DelayNs + Clone,
+    XSHUT: OutputPin,
+    MODE: Ranging,
+{
+    /// Stops ranging on every sensor, in bring up order
+    ///
+    /// A sensor failing to stop is power cycled instead, which stops it too but resets its
+    /// configuration. The idle array is returned in any case, along with the first error if any;
+    /// a sensor which could not be power cycled either is to be recovered with `recover`.
+    pub async fn stop_continuous(
+        mut self,
+    ) -> Result<SensorArray<I2C, D, XSHUT, N, Idle>, (SensorArray<I2C, D, XSHUT, N, Idle>, Error<E>)>
+    {
+        let mut result = Ok(());
+        for sensor in self.sensors.iter_mut() {
+            if let Err(e) = sensor.stop_ranging().await {
+                sensor.power_cycle().await.ok();
+                result = result.and(Err(e));
+            }
+        }
+
+        let array = SensorArray {
+            sensors: self.sensors.map(VL53L0X::into_mode),
+        };
+        match result {
+            Ok(()) => Ok(array),
+            Err(e) => Err((array, e)),
+        }
+    }
+
+    /// Power cycles the sensor at `index`, moves it back to its address and starts ranging again
+    /// with the same period, without disturbing the others, e.g. when it stops answering. Its
+    /// configuration is back to its default.
+    ///
+    /// Panics if `index` is out of range.
+    pub async fn recover(&mut self, index: usize) -> Result<(), Error<E>> {
+        self.sensors[index].reset_ranging().await
+    }
+}
@@ -0,0 +1,630 @@
+//! Calibration routines
+
+use ehal::delay::DelayNs;
+use ehal::i2c::{I2c, SevenBitAddress};
+#[cfg(feature = "async")]
+use embedded_hal_async::{delay::DelayNs as AsyncDelayNs, i2c::I2c as AsyncI2c};
+
+#[cfg(feature = "async")]
+use super::VL53L0XAsync;
+use super::VL53L0X;
+use crate::{
+    decode_q9_7, CalibrationData, Error, Idle, LimitCheck, RangeStatus, Ranging, RefCalibration,
+    ReferenceSpads, Register,
+};
+
+#[maybe_async_cfg::maybe(
+    sync(keep_self),
+    async(feature = "async"),
+    idents(
+        I2c(sync, async = "AsyncI2c"),
+        DelayNs(sync, async = "AsyncDelayNs"),
+        VL53L0X(sync, async = "VL53L0XAsync")
+    )
+)]
+impl<I2C, D, MODE, XSHUT, E> VL53L0X<I2C, D, MODE, XSHUT>
+where
+    I2C: I2c<SevenBitAddress, Error = E>,
+    D: DelayNs,
+{
+    // Checks and writes the range offset
+    async fn apply_offset_calibration_um(&mut self, offset_um: i32) -> Result<(), Error<E>> {
+        // 12 bit two's complement, in 0.25 mm steps
+        if !(OFFSET_MIN_UM..=OFFSET_MAX_UM).contains(&offset_um) {
+            return Err(Error::InvalidOffset);
+        }
+
+        let offset = offset_um / 250;
+        self.write_16bit(
+            Register::ALGO_PART_TO_PART_RANGE_OFFSET_MM,
+            (offset as u16) & 0x0FFF,
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    /// Returns the range offset in micrometers
+    pub async fn get_offset_calibration_um(&mut self) -> Result<i32, E> {
+        let offset = self
+            .read_16bit(Register::ALGO_PART_TO_PART_RANGE_OFFSET_MM)
+            .await?
+            & 0x0FFF;
+        // sign extension of the 12 bit value
+        Ok((((offset << 4) as i16) >> 4) as i32 * 250)
+    }
+
+    // Checks and stores the crosstalk compensation rate, written only while the compensation is
+    // enabled
+    async fn apply_xtalk_compensation_rate_mcps(&mut self, rate_mcps: f32) -> Result<(), Error<E>> {
+        if !(0.0..=XTALK_RATE_MAX_MCPS).contains(&rate_mcps) {
+            return Err(Error::InvalidXtalkCompensationRate);
+        }
+
+        self.xtalk_compensation_rate_mcps = rate_mcps;
+        if self.xtalk_compensation_enabled {
+            self.write_16bit(
+                Register::CROSSTALK_COMPENSATION_PEAK_RATE_MCPS,
+                encode_xtalk_rate(rate_mcps),
+            )
+            .await?;
+        }
+
+        Ok(())
+    }
+
+    /// Returns the crosstalk compensation rate per SPAD, in MCPS
+    pub fn get_xtalk_compensation_rate_mcps(&self) -> f32 {
+        self.xtalk_compensation_rate_mcps
+    }
+
+    // Writes the crosstalk compensation rate, or 0 to disable the compensation
+    async fn apply_xtalk_compensation_enable(&mut self, enable: bool) -> Result<(), Error<E>> {
+        let rate = if enable {
+            encode_xtalk_rate(self.xtalk_compensation_rate_mcps)
+        } else {
+            0
+        };
+        self.write_16bit(Register::CROSSTALK_COMPENSATION_PEAK_RATE_MCPS, rate)
+            .await?;
+        self.xtalk_compensation_enabled = enable;
+
+        Ok(())
+    }
+
+    /// Returns whether the crosstalk compensation is enabled
+    pub fn is_xtalk_compensation_enabled(&self) -> bool {
+        self.xtalk_compensation_enabled
+    }
+
+    /// Returns the VHV and phase reference calibration values in use
+    pub async fn get_ref_calibration(&mut self) -> Result<RefCalibration, E> {
+        self.write_byte(0xFF, 0x01).await?;
+        self.write_byte(0x00, 0x00).await?;
+        self.write_byte(0xFF, 0x00).await?;
+
+        let vhv_settings = self.read_byte(0xCB).await;
+        let phase_cal = self.read_byte(0xEE).await;
+
+        self.write_byte(0xFF, 0x01).await?;
+        self.write_byte(0x00, 0x01).await?;
+        self.write_byte(0xFF, 0x00).await?;
+
+        Ok(RefCalibration {
+            vhv_settings: vhv_settings?,
+            phase_cal: phase_cal? & 0xEF,
+        })
+    }
+
+    // Writes the VHV and phase calibration values, keeping the top bit of the phase register
+    async fn apply_ref_calibration(
+        &mut self,
+        ref_calibration: RefCalibration,
+    ) -> Result<(), Error<E>> {
+        self.write_byte(0xFF, 0x01).await?;
+        self.write_byte(0x00, 0x00).await?;
+        self.write_byte(0xFF, 0x00).await?;
+
+        self.write_byte(0xCB, ref_calibration.vhv_settings).await?;
+        let phase_cal = self.read_byte(0xEE).await?;
+        self.write_byte(0xEE, (phase_cal & 0x80) | ref_calibration.phase_cal)
+            .await?;
+
+        self.write_byte(0xFF, 0x01).await?;
+        self.write_byte(0x00, 0x01).await?;
+        self.write_byte(0xFF, 0x00).await?;
+
+        Ok(())
+    }
+
+    /// Returns the calibration data in use, to be stored and restored later with
+    /// `set_calibration_data`
+    pub async fn get_calibration_data(&mut self) -> Result<CalibrationData, E> {
+        let ref_spad_map = self
+            .read_6bytes(Register::GLOBAL_CONFIG_SPAD_ENABLES_REF_0)
+            .await?;
+        // the enabled SPADs are all of the type of the first one
+        let reference_spads = ReferenceSpads {
+            count: ref_spad_map.iter().map(|b| b.count_ones() as u8).sum(),
+            is_aperture: next_good_spad(&ref_spad_map, 0).is_some_and(is_aperture_spad),
+        };
+
+        Ok(CalibrationData {
+            reference_spads,
+            ref_spad_map,
+            ref_calibration: self.get_ref_calibration().await?,
+            offset_um: self.get_offset_calibration_um().await?,
+            xtalk_compensation_rate_mcps: self.xtalk_compensation_rate_mcps,
+            xtalk_compensation_enabled: self.xtalk_compensation_enabled,
+        })
+    }
+
+    // Applies calibration data, also when the sensor is booted again by a power cycle
+    pub(super) async fn apply_calibration_data(
+        &mut self,
+        data: &CalibrationData,
+    ) -> Result<(), Error<E>> {
+        if !(0.0..=XTALK_RATE_MAX_MCPS).contains(&data.xtalk_compensation_rate_mcps) {
+            return Err(Error::InvalidXtalkCompensationRate);
+        }
+        if !(OFFSET_MIN_UM..=OFFSET_MAX_UM).contains(&data.offset_um) {
+            return Err(Error::InvalidOffset);
+        }
+
+        self.ref_spad_setup().await?;
+        self.write_6bytes(
+            Register::GLOBAL_CONFIG_SPAD_ENABLES_REF_0,
+            data.ref_spad_map,
+        )
+        .await?;
+
+        self.apply_ref_calibration(data.ref_calibration).await?;
+        self.apply_offset_calibration_um(data.offset_um).await?;
+        self.apply_xtalk_compensation_rate_mcps(data.xtalk_compensation_rate_mcps)
+            .await?;
+        self.apply_xtalk_compensation_enable(data.xtalk_compensation_enabled)
+            .await?;
+
+        Ok(())
+    }
+
+    // Enables the reference SPADs, also when the sensor is booted again by a power cycle
+    pub(super) async fn apply_reference_spads(
+        &mut self,
+        spads: ReferenceSpads,
+    ) -> Result<(), Error<E>> {
+        self.ref_spad_setup().await?;
+
+        let mut spad_map = [0; 6];
+        let mut spad_index = 0;
+        if spads.is_aperture {
+            while !is_aperture_spad(spad_index) && spad_index < MAX_REF_SPAD_COUNT {
+                spad_index += 1;
+            }
+        }
+        self.enable_ref_spads(&mut spad_map, spads.is_aperture, spad_index, spads.count)
+            .await?;
+
+        Ok(())
+    }
+    async fn ref_spad_setup(&mut self) -> Result<(), E> {
+        self.write_byte(0xFF, 0x01).await?;
+        self.write_register(Register::DYNAMIC_SPAD_REF_EN_START_OFFSET, 0x00)
+            .await?;
+        self.write_register(Register::DYNAMIC_SPAD_NUM_REQUESTED_REF_SPAD, 0x2C)
+            .await?;
+        self.write_byte(0xFF, 0x00).await?;
+        self.write_register(
+            Register::GLOBAL_CONFIG_REF_EN_START_SELECT,
+            REF_SPAD_START_SELECT,
+        )
+        .await
+    }
+
+    // Enables `count` good SPADs of the given type from `spad_index`, writes the map and checks it
+    // back; returns the index following the last enabled SPAD
+    async fn enable_ref_spads(
+        &mut self,
+        spad_map: &mut [u8; 6],
+        is_aperture: bool,
+        mut spad_index: usize,
+        count: u8,
+    ) -> Result<usize, Error<E>> {
+        for _ in 0..count {
+            let next_spad = next_good_spad(&self.ref_good_spad_map, spad_index)
+                .ok_or(Error::CalibrationFailed)?;
+            if is_aperture_spad(next_spad) != is_aperture {
+                return Err(Error::CalibrationFailed);
+            }
+            enable_spad(spad_map, next_spad);
+            spad_index = next_spad + 1;
+        }
+
+        self.write_6bytes(Register::GLOBAL_CONFIG_SPAD_ENABLES_REF_0, *spad_map)
+            .await?;
+        if self
+            .read_6bytes(Register::GLOBAL_CONFIG_SPAD_ENABLES_REF_0)
+            .await?
+            != *spad_map
+        {
+            return Err(Error::CalibrationFailed);
+        }
+
+        Ok(spad_index)
+    }
+
+    // Performs the VHV and phase calibrations, saving and restoring the sequence config
+    pub(super) async fn ref_calibration(&mut self) -> Result<(), Error<E>> {
+        let sequence_config = self.read_register(Register::SYSTEM_SEQUENCE_CONFIG).await?;
+
+        self.write_register(Register::SYSTEM_SEQUENCE_CONFIG, 0x01)
+            .await?;
+        self.perform_single_ref_calibration(0x40).await?;
+
+        self.write_register(Register::SYSTEM_SEQUENCE_CONFIG, 0x02)
+            .await?;
+        self.perform_single_ref_calibration(0x00).await?;
+
+        self.write_register(Register::SYSTEM_SEQUENCE_CONFIG, sequence_config)
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[maybe_async_cfg::maybe(
+    sync(keep_self),
+    async(feature = "async"),
+    idents(
+        I2c(sync, async = "AsyncI2c"),
+        DelayNs(sync, async = "AsyncDelayNs"),
+        VL53L0X(sync, async = "VL53L0XAsync")
+    )
+)]
+impl<I2C, D, XSHUT, E> VL53L0X<I2C, D, Idle, XSHUT>
+where
+    I2C: I2c<SevenBitAddress, Error = E>,
+    D: DelayNs,
+{
+    /// Performs the offset calibration against a target at a known distance, and applies the
+    /// resulting offset. Returns the offset in micrometers, to be stored and restored later with
+    /// `set_offset_calibration_um`.
+    ///
+    /// `sample_count` single measurements are taken and the valid ones averaged; ST recommends 50
+    /// measurements of a white target at 100 mm, in the dark. Returns `Error::InvalidOffset` if
+    /// the measured offset is out of the range the device can apply; the previous offset is
+    /// applied again when the calibration fails.
+    pub async fn perform_offset_calibration(
+        &mut self,
+        target_distance_mm: u16,
+        sample_count: u16,
+    ) -> Result<i32, Error<E>> {
+        // TCC is disabled during the calibration, as in the ST API
+        let sequence_config = self.read_register(Register::SYSTEM_SEQUENCE_CONFIG).await?;
+        let tcc_enabled = sequence_config & 0x10 != 0;
+        if tcc_enabled {
+            self.write_register(Register::SYSTEM_SEQUENCE_CONFIG, sequence_config & !0x10)
+                .await?;
+            let mtbm = self.measurement_timing_budget_microseconds;
+            self.set_measurement_timing_budget(mtbm).await?;
+        }
+        let range_ignore_enabled = self.limit_checks.enabled(LimitCheck::RangeIgnoreThreshold);
+        self.set_limit_check_enable(LimitCheck::RangeIgnoreThreshold, false)
+            .await?;
+
+        // measure without any offset applied, keeping the previous offset if the calibration fails
+        let previous_offset_um = self.get_offset_calibration_um().await?;
+        self.set_offset_calibration_um(0).await?;
+
+        let result = match self.average_measurements(sample_count).await {
+            Ok((mean_range_mm, _, _)) => {
+                let offset_um = (target_distance_mm as i32 - mean_range_mm as i32) * 1000;
+                self.set_offset_calibration_um(offset_um)
+                    .await
+                    .map(|()| offset_um)
+            }
+            Err(error) => Err(error),
+        };
+        if result.is_err() {
+            self.set_offset_calibration_um(previous_offset_um).await?;
+        }
+
+        if tcc_enabled {
+            self.write_register(Register::SYSTEM_SEQUENCE_CONFIG, sequence_config)
+                .await?;
+            let mtbm = self.measurement_timing_budget_microseconds;
+            self.set_measurement_timing_budget(mtbm).await?;
+        }
+        self.set_limit_check_enable(LimitCheck::RangeIgnoreThreshold, range_ignore_enabled)
+            .await?;
+
+        result
+    }
+
+    /// Performs the crosstalk calibration against a target at a known distance, then sets and
+    /// enables the resulting compensation rate. Returns the rate per SPAD in MCPS, to be stored and
+    /// restored later with `set_xtalk_compensation_rate_mcps`.
+    ///
+    /// `sample_count` single measurements are taken and the valid ones averaged; ST recommends 50
+    /// measurements of a grey target placed where the range starts to be underestimated, beyond
+    /// 600 mm, in the dark. The offset calibration must be done first.
+    pub async fn perform_xtalk_calibration(
+        &mut self,
+        target_distance_mm: u16,
+        sample_count: u16,
+    ) -> Result<f32, Error<E>> {
+        if target_distance_mm == 0 {
+            return Err(Error::CalibrationFailed);
+        }
+
+        self.enable_xtalk_compensation(false).await?;
+
+        let (mean_range_mm, mean_signal_rate_mcps, mean_spad_count) =
+            self.average_measurements(sample_count).await?;
+        // the effective SPAD count is 8.8, rounded to a whole number of SPADs
+        let spad_count = (mean_spad_count + 0x80) >> 8;
+        if spad_count == 0 {
+            return Err(Error::CalibrationFailed);
+        }
+
+        // the crosstalk makes the target look closer, in proportion to its share of the signal
+        let signal_rate_per_spad = decode_q9_7(mean_signal_rate_mcps) / spad_count as f32;
+        let xtalk_correction = 1.0 - mean_range_mm as f32 / target_distance_mm as f32;
+        let rate_mcps = (signal_rate_per_spad * xtalk_correction).clamp(0.0, XTALK_RATE_MAX_MCPS);
+
+        self.set_xtalk_compensation_rate_mcps(rate_mcps).await?;
+        self.enable_xtalk_compensation(true).await?;
+
+        Ok(rate_mcps)
+    }
+
+    /// Performs the VHV (voltage high voltage) and phase reference calibrations, and returns the
+    /// resulting values. ST recommends doing it again whenever the temperature changes by more than
+    /// 8 °C.
+    pub async fn perform_ref_calibration(&mut self) -> Result<RefCalibration, Error<E>> {
+        self.ref_calibration().await?;
+
+        Ok(self.get_ref_calibration().await?)
+    }
+
+    /// Performs the reference SPAD management as the ST API does, selecting the reference SPADs
+    /// whose signal rate is the closest to the 20 MCPS target, and returns the selection. This is
+    /// needed when a cover glass changes the reference signal; the result can be stored and
+    /// applied at boot with `set_reference_spads`.
+    ///
+    /// The reference calibration is performed as part of the management, as it must be done
+    /// first.
+    pub async fn perform_ref_spad_management(&mut self) -> Result<ReferenceSpads, Error<E>> {
+        self.ref_spad_setup().await?;
+        // POWER_MANAGEMENT_GO1_POWER_FORCE
+        self.write_byte(0x80, 0x00).await?;
+
+        self.ref_calibration().await?;
+
+        // start with the minimum number of non-aperture SPADs
+        let mut spad_map = [0; 6];
+        let mut is_aperture = false;
+        let mut spad_index = self
+            .enable_ref_spads(&mut spad_map, false, 0, MIN_REF_SPAD_COUNT)
+            .await?;
+        let mut signal_rate = self.measure_ref_signal_rate().await?;
+
+        if signal_rate > TARGET_REF_SIGNAL_RATE {
+            // signal rate too high, switch to aperture SPADs
+            spad_map = [0; 6];
+            spad_index = 0;
+            while !is_aperture_spad(spad_index) && spad_index < MAX_REF_SPAD_COUNT {
+                spad_index += 1;
+            }
+            is_aperture = true;
+            spad_index = self
+                .enable_ref_spads(&mut spad_map, true, spad_index, MIN_REF_SPAD_COUNT)
+                .await?;
+            signal_rate = self.measure_ref_signal_rate().await?;
+        }
+
+        let mut count = MIN_REF_SPAD_COUNT;
+        if signal_rate < TARGET_REF_SIGNAL_RATE {
+            // add SPADs one at a time until the signal rate is just above the target
+            let mut last_spad_map = spad_map;
+            let mut last_signal_rate_diff = TARGET_REF_SIGNAL_RATE - signal_rate;
+            loop {
+                let next_spad = next_good_spad(&self.ref_good_spad_map, spad_index)
+                    .ok_or(Error::CalibrationFailed)?;
+                // aperture and non-aperture SPADs cannot be combined
+                if is_aperture_spad(next_spad) != is_aperture {
+                    break;
+                }
+
+                count += 1;
+                enable_spad(&mut spad_map, next_spad);
+                spad_index = next_spad + 1;
+                self.write_6bytes(Register::GLOBAL_CONFIG_SPAD_ENABLES_REF_0, spad_map)
+                    .await?;
+
+                signal_rate = self.measure_ref_signal_rate().await?;
+                let signal_rate_diff = signal_rate.abs_diff(TARGET_REF_SIGNAL_RATE);
+                if signal_rate > TARGET_REF_SIGNAL_RATE {
+                    // keep the map giving the signal rate closest to the target
+                    if signal_rate_diff > last_signal_rate_diff {
+                        self.write_6bytes(
+                            Register::GLOBAL_CONFIG_SPAD_ENABLES_REF_0,
+                            last_spad_map,
+                        )
+                        .await?;
+                        count -= 1;
+                    }
+                    break;
+                }
+                last_signal_rate_diff = signal_rate_diff;
+                last_spad_map = spad_map;
+            }
+        }
+
+        Ok(ReferenceSpads { count, is_aperture })
+    }
+
+    // Returns the reference signal rate (Q9.7) of a single measurement with only the pre-range and
+    // final range steps enabled
+    async fn measure_ref_signal_rate(&mut self) -> Result<u16, Error<E>> {
+        let sequence_config = self.read_register(Register::SYSTEM_SEQUENCE_CONFIG).await?;
+        self.write_register(Register::SYSTEM_SEQUENCE_CONFIG, 0xC0)
+            .await?;
+
+        self.read_measurement_single_blocking().await?;
+
+        // RESULT_PEAK_SIGNAL_RATE_REF, on page 1
+        let mut buffer = [0; 2];
+        self.write_byte(0xFF, 0x01).await?;
+        self.com
+            .write_read(self.address, &[0xB6], &mut buffer)
+            .await?;
+        self.write_byte(0xFF, 0x00).await?;
+
+        self.write_register(Register::SYSTEM_SEQUENCE_CONFIG, sequence_config)
+            .await?;
+
+        Ok(u16::from_be_bytes(buffer))
+    }
+
+    // Averages the valid measurements among `sample_count` single measurements; returns the mean
+    // range in mm, signal rate (Q9.7) and effective SPAD count (8.8)
+    async fn average_measurements(
+        &mut self,
+        sample_count: u16,
+    ) -> Result<(u16, u16, u32), Error<E>> {
+        let mut range_sum: u32 = 0;
+        let mut signal_rate_sum: u32 = 0;
+        let mut spad_count_sum: u32 = 0;
+        let mut count: u32 = 0;
+        for _ in 0..sample_count {
+            let measurement = self.read_measurement_single_blocking().await?;
+            if measurement.range_status == RangeStatus::Valid {
+                range_sum += measurement.range_mm as u32;
+                signal_rate_sum += measurement.signal_rate_mcps as u32;
+                spad_count_sum += measurement.effective_spad_rtn_count as u32;
+                count += 1;
+            }
+        }
+
+        if count == 0 {
+            return Err(Error::CalibrationFailed);
+        }
+
+        Ok((
+            ((range_sum + count / 2) / count) as u16,
+            ((signal_rate_sum + count / 2) / count) as u16,
+            (spad_count_sum + count / 2) / count,
+        ))
+    }
+
+    /// Sets the range offset in micrometers, added by the device to every range. The offset is
+    /// applied with a 0.25 mm resolution; `Error::InvalidOffset` is returned if it is out of the
+    /// -512 mm..511.75 mm range.
+    pub async fn set_offset_calibration_um(&mut self, offset_um: i32) -> Result<(), Error<E>> {
+        self.apply_offset_calibration_um(offset_um).await
+    }
+
+    /// Sets the crosstalk compensation rate per SPAD, in MCPS (mega counts per second). Returns
+    /// `Error::InvalidXtalkCompensationRate` if the rate is out of range.
+    ///
+    /// The rate is written to the device only while the compensation is enabled.
+    pub async fn set_xtalk_compensation_rate_mcps(
+        &mut self,
+        rate_mcps: f32,
+    ) -> Result<(), Error<E>> {
+        self.apply_xtalk_compensation_rate_mcps(rate_mcps).await
+    }
+
+    /// Enables or disables the crosstalk compensation. Disabled by default.
+    pub async fn enable_xtalk_compensation(&mut self, enable: bool) -> Result<(), Error<E>> {
+        self.apply_xtalk_compensation_enable(enable).await
+    }
+
+    /// Sets the VHV and phase reference calibration values, e.g. as returned by a previous
+    /// `perform_ref_calibration`
+    pub async fn set_ref_calibration(
+        &mut self,
+        ref_calibration: RefCalibration,
+    ) -> Result<(), Error<E>> {
+        self.apply_ref_calibration(ref_calibration).await
+    }
+
+    /// Applies calibration data returned by a previous `get_calibration_data`, instead of
+    /// calibrating again. Returns `Error::InvalidXtalkCompensationRate` if the crosstalk
+    /// compensation rate is out of range, and `Error::InvalidOffset` if the offset is.
+    ///
+    /// The reference SPADs are enabled from `ref_spad_map`.
+    pub async fn set_calibration_data(&mut self, data: &CalibrationData) -> Result<(), Error<E>> {
+        self.apply_calibration_data(data).await
+    }
+
+    /// Enables the given reference SPADs, e.g. as returned by a previous
+    /// `perform_ref_spad_management`. At boot the selection programmed in the factory is applied.
+    ///
+    /// The reference calibration must be performed again afterwards.
+    pub async fn set_reference_spads(&mut self, spads: ReferenceSpads) -> Result<(), Error<E>> {
+        self.apply_reference_spads(spads).await
+    }
+}
+
+// first reference SPAD of the map, in the global SPAD array
+const REF_SPAD_START_SELECT: u8 = 0xB4;
+const MIN_REF_SPAD_COUNT: u8 = 3;
+const MAX_REF_SPAD_COUNT: usize = 44;
+// 20 MCPS, Q9.7
+const TARGET_REF_SIGNAL_RATE: u16 = 0x0A00;
+
+// The reference SPAD map covers 48 SPADs from REF_SPAD_START_SELECT; in the global array, only
+// the third quadrant (SPADs 128 to 191) holds non-aperture SPADs
+fn is_aperture_spad(spad_index: usize) -> bool {
+    (REF_SPAD_START_SELECT as usize + spad_index) >> 6 != 2
+}
+
+fn next_good_spad(good_spad_map: &[u8; 6], spad_index: usize) -> Option<usize> {
+    (spad_index..48).find(|&i| (good_spad_map[i / 8] >> (i % 8)) & 0x1 > 0)
+}
+
+fn enable_spad(spad_map: &mut [u8; 6], spad_index: usize) {
+    spad_map[spad_index / 8] |= 1 << (spad_index % 8);
+}
+
+#[maybe_async_cfg::maybe(
+    sync(keep_self),
+    async(feature = "async"),
+    idents(
+        I2c(sync, async = "AsyncI2c"),
+        DelayNs(sync, async = "AsyncDelayNs"),
+        VL53L0X(sync, async = "VL53L0XAsync")
+    )
+)]
+impl<I2C, D, MODE, XSHUT, E> VL53L0X<I2C, D, MODE, XSHUT>
+where
+    I2C: I2c<SevenBitAddress, Error = E>,
+    D: DelayNs,
+    MODE: Ranging,
+{
+    /// Performs the VHV (voltage high voltage) and phase reference calibrations, and returns the
+    /// resulting values. Ranging is stopped for the calibration and started again afterwards in
+    /// the same mode, with the same period.
+    pub async fn perform_ref_calibration(&mut self) -> Result<RefCalibration, Error<E>> {
+        self.stop_ranging().await?;
+        self.ref_calibration().await?;
+        let calibration = self.get_ref_calibration().await?;
+        self.restart_ranging().await?;
+
+        Ok(calibration)
+    }
+}
+
+// Range offset limits, 12 bit two's complement in 0.25 mm steps
+const OFFSET_MIN_UM: i32 = -512_000;
+const OFFSET_MAX_UM: i32 = 511_750;
+
+// Largest rate the 3.13 fixed point format of the crosstalk register can hold
+const XTALK_RATE_MAX_MCPS: f32 = 7.99;
+
+// 3.13 fixed point format
+fn encode_xtalk_rate(rate_mcps: f32) -> u16 {
+    (rate_mcps * ((1 << 13) as f32)) as u16
+}
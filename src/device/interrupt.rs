@@ -0,0 +1,144 @@
+//! GPIO1 interrupt configuration
+
+use ehal::delay::DelayNs;
+use ehal::i2c::{I2c, SevenBitAddress};
+#[cfg(feature = "async")]
+use embedded_hal_async::{delay::DelayNs as AsyncDelayNs, i2c::I2c as AsyncI2c};
+
+#[cfg(feature = "async")]
+use super::VL53L0XAsync;
+use super::VL53L0X;
+use crate::{Error, Idle, InterruptMode, InterruptPolarity, Register};
+
+// Largest threshold the registers can hold, in mm; they hold 12 bits in 2 mm steps
+const THRESHOLD_MAX_MM: u16 = 0x0FFF * 2;
+
+#[maybe_async_cfg::maybe(
+    sync(keep_self),
+    async(feature = "async"),
+    idents(
+        I2c(sync, async = "AsyncI2c"),
+        DelayNs(sync, async = "AsyncDelayNs"),
+        VL53L0X(sync, async = "VL53L0XAsync")
+    )
+)]
+impl<I2C, D, MODE, XSHUT, E> VL53L0X<I2C, D, MODE, XSHUT>
+where
+    I2C: I2c<SevenBitAddress, Error = E>,
+    D: DelayNs,
+{
+    // Writes the GPIO1 interrupt condition and clears the interrupt
+    pub(super) async fn apply_interrupt_mode(
+        &mut self,
+        mode: InterruptMode,
+    ) -> Result<(), Error<E>> {
+        self.write_register(Register::SYSTEM_INTERRUPT_CONFIG_GPIO, mode as u8)
+            .await?;
+        self.write_register(Register::SYSTEM_INTERRUPT_CLEAR, 0x01)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Returns the condition asserting the GPIO1 interrupt output
+    pub async fn get_interrupt_mode(&mut self) -> Result<InterruptMode, E> {
+        Ok(
+            match self
+                .read_register(Register::SYSTEM_INTERRUPT_CONFIG_GPIO)
+                .await?
+                & 0x07
+            {
+                1 => InterruptMode::LevelLow,
+                2 => InterruptMode::LevelHigh,
+                3 => InterruptMode::OutOfWindow,
+                4 => InterruptMode::NewSampleReady,
+                _ => InterruptMode::Disabled,
+            },
+        )
+    }
+
+    // Sets the active level of GPIO1
+    pub(super) async fn apply_interrupt_polarity(
+        &mut self,
+        polarity: InterruptPolarity,
+    ) -> Result<(), Error<E>> {
+        let high = self
+            .read_register(Register::GPIO_HV_MUX_ACTIVE_HIGH)
+            .await?
+            & !0x10;
+        let active_high = match polarity {
+            InterruptPolarity::ActiveLow => 0x00,
+            InterruptPolarity::ActiveHigh => 0x10,
+        };
+        self.write_register(Register::GPIO_HV_MUX_ACTIVE_HIGH, high | active_high)
+            .await?;
+        self.interrupt_polarity = polarity;
+
+        Ok(())
+    }
+
+    /// Returns the level of the GPIO1 interrupt output while the interrupt is asserted
+    pub fn get_interrupt_polarity(&self) -> InterruptPolarity {
+        self.interrupt_polarity
+    }
+
+    /// Returns the low and high range thresholds of the threshold interrupt modes, in mm
+    pub async fn get_interrupt_thresholds(&mut self) -> Result<(u16, u16), E> {
+        let low = self.read_16bit(Register::SYSTEM_THRESH_LOW).await? & 0x0FFF;
+        let high = self.read_16bit(Register::SYSTEM_THRESH_HIGH).await? & 0x0FFF;
+
+        Ok((low * 2, high * 2))
+    }
+}
+
+#[maybe_async_cfg::maybe(
+    sync(keep_self),
+    async(feature = "async"),
+    idents(
+        I2c(sync, async = "AsyncI2c"),
+        DelayNs(sync, async = "AsyncDelayNs"),
+        VL53L0X(sync, async = "VL53L0XAsync")
+    )
+)]
+impl<I2C, D, XSHUT, E> VL53L0X<I2C, D, Idle, XSHUT>
+where
+    I2C: I2c<SevenBitAddress, Error = E>,
+    D: DelayNs,
+{
+    /// Sets the condition asserting the GPIO1 interrupt output. The threshold modes compare the
+    /// range of each measurement with the thresholds set by `set_interrupt_thresholds`.
+    pub async fn set_interrupt_mode(&mut self, mode: InterruptMode) -> Result<(), Error<E>> {
+        self.apply_interrupt_mode(mode).await
+    }
+
+    /// Sets the level of the GPIO1 interrupt output while the interrupt is asserted. The
+    /// `_interrupt` reading methods wait for this level.
+    pub async fn set_interrupt_polarity(
+        &mut self,
+        polarity: InterruptPolarity,
+    ) -> Result<(), Error<E>> {
+        self.apply_interrupt_polarity(polarity).await
+    }
+
+    /// Sets the low and high range thresholds of the threshold interrupt modes, in mm. Returns
+    /// `Error::InvalidInterruptThresholds` if a threshold is above 8190 mm or the low threshold is
+    /// above the high one.
+    ///
+    /// The thresholds are applied with a 2 mm resolution.
+    pub async fn set_interrupt_thresholds(
+        &mut self,
+        low_mm: u16,
+        high_mm: u16,
+    ) -> Result<(), Error<E>> {
+        if low_mm > high_mm || high_mm > THRESHOLD_MAX_MM {
+            return Err(Error::InvalidInterruptThresholds);
+        }
+
+        self.write_16bit(Register::SYSTEM_THRESH_LOW, low_mm / 2)
+            .await?;
+        self.write_16bit(Register::SYSTEM_THRESH_HIGH, high_mm / 2)
+            .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,165 @@
+//! XSHUT pin control
+
+use ehal::delay::DelayNs;
+use ehal::digital::OutputPin;
+use ehal::i2c::{I2c, SevenBitAddress};
+#[cfg(feature = "async")]
+use embedded_hal_async::{delay::DelayNs as AsyncDelayNs, i2c::I2c as AsyncI2c};
+
+#[cfg(feature = "async")]
+use super::VL53L0XAsync;
+use super::VL53L0X;
+use crate::{limit_check::LimitChecks, Error, Idle, InterruptPolarity, Ranging, ADDRESS_DEFAULT};
+
+#[maybe_async_cfg::maybe(
+    sync(keep_self),
+    async(feature = "async"),
+    idents(
+        I2c(sync, async = "AsyncI2c"),
+        DelayNs(sync, async = "AsyncDelayNs"),
+        VL53L0X(sync, async = "VL53L0XAsync")
+    )
+)]
+impl<I2C, D, MODE, XSHUT, E> VL53L0X<I2C, D, MODE, XSHUT>
+where
+    I2C: I2c<SevenBitAddress, Error = E>,
+    D: DelayNs,
+    XSHUT: OutputPin,
+{
+    // Drives XSHUT low
+    async fn shut_down(&mut self) -> Result<(), Error<E>> {
+        self.xshut.set_low().map_err(|_| Error::PinError)
+    }
+
+    // Drives XSHUT high, then boots and initializes the sensor as `new` does
+    async fn boot(&mut self) -> Result<(), Error<E>> {
+        self.xshut.set_high().map_err(|_| Error::PinError)?;
+
+        // the sensor boots at the default address, with its default configuration
+        let address = self.address;
+        self.address = ADDRESS_DEFAULT;
+        self.limit_checks = LimitChecks::default();
+        self.xtalk_compensation_rate_mcps = 0.0;
+        self.xtalk_compensation_enabled = false;
+        self.interrupt_polarity = InterruptPolarity::ActiveLow;
+
+        let mut result = self.init().await;
+        if result.is_ok() && address != ADDRESS_DEFAULT {
+            result = self.set_device_address(address).await;
+        }
+        // keep the address even if booting failed, so that the next power cycle still moves the
+        // sensor to it rather than leaving it at the default address
+        self.address = address;
+
+        result
+    }
+
+    // Shuts the sensor down, then boots it again
+    pub(super) async fn power_cycle(&mut self) -> Result<(), Error<E>> {
+        self.shut_down().await?;
+        // let the sensor shut down before booting it again
+        self.delay.delay_us(1_000).await;
+        self.boot().await
+    }
+}
+
+#[maybe_async_cfg::maybe(
+    sync(keep_self),
+    async(feature = "async"),
+    idents(
+        I2c(sync, async = "AsyncI2c"),
+        DelayNs(sync, async = "AsyncDelayNs"),
+        VL53L0X(sync, async = "VL53L0XAsync")
+    )
+)]
+impl<I2C, D, XSHUT, E> VL53L0X<I2C, D, Idle, XSHUT>
+where
+    I2C: I2c<SevenBitAddress, Error = E>,
+    D: DelayNs,
+    XSHUT: OutputPin,
+{
+    /// Creates a sensor whose XSHUT pin is driven by the driver. The sensor is power cycled, so
+    /// that it boots at the default address even if it kept a previously assigned one (e.g. after
+    /// a reset of the MCU alone), then initialized and moved to `address`.
+    pub async fn new_with_xshut(
+        i2c: I2C,
+        delay: D,
+        xshut: XSHUT,
+        address: u8,
+        io_mode2v8: bool,
+    ) -> Result<VL53L0X<I2C, D, Idle, XSHUT>, Error<E>> {
+        let mut chip = VL53L0X::from_parts(i2c, delay, xshut, address, io_mode2v8, None);
+        chip.power_cycle().await?;
+        Ok(chip)
+    }
+
+    /// Drives XSHUT low, shutting the sensor down; its configuration is lost
+    pub async fn power_down(&mut self) -> Result<(), Error<E>> {
+        self.shut_down().await
+    }
+
+    /// Drives XSHUT high and waits for the sensor to boot, then initializes it as `new` does and
+    /// moves it back to its address. Calibration data given at creation is applied again; any
+    /// other configuration is back to its default.
+    pub async fn power_up(&mut self) -> Result<(), Error<E>> {
+        self.boot().await
+    }
+
+    /// Power cycles the sensor through XSHUT, e.g. to recover it when it stops answering
+    pub async fn hardware_reset(&mut self) -> Result<(), Error<E>> {
+        self.power_cycle().await
+    }
+}
+
+#[maybe_async_cfg::maybe(
+    sync(keep_self),
+    async(feature = "async"),
+    idents(
+        I2c(sync, async = "AsyncI2c"),
+        DelayNs(sync, async = "AsyncDelayNs"),
+        VL53L0X(sync, async = "VL53L0XAsync")
+    )
+)]
+impl<I2C, D, MODE, XSHUT, E> VL53L0X<I2C, D, MODE, XSHUT>
+where
+    I2C: I2c<SevenBitAddress, Error = E>,
+    D: DelayNs,
+    XSHUT: OutputPin,
+    MODE: Ranging,
+{
+    /// Drives XSHUT low, stopping ranging and shutting the sensor down; its configuration is lost.
+    /// The sensor is then idle, to be booted again with `power_up`.
+    ///
+    /// On error the driver is returned in its current mode along with the error.
+    pub async fn power_down(
+        mut self,
+    ) -> Result<VL53L0X<I2C, D, Idle, XSHUT>, (VL53L0X<I2C, D, MODE, XSHUT>, Error<E>)> {
+        match self.shut_down().await {
+            Ok(()) => Ok(self.into_mode()),
+            Err(e) => Err((self, e)),
+        }
+    }
+
+    /// Power cycles the sensor through XSHUT, stopping ranging, e.g. to recover it when it stops
+    /// answering. Its configuration is back to its default, as after `power_up`.
+    ///
+    /// The sensor is not ranging anymore once it has been shut down, so the idle driver is
+    /// returned on error as well, to try again.
+    pub async fn hardware_reset(
+        self,
+    ) -> Result<VL53L0X<I2C, D, Idle, XSHUT>, (VL53L0X<I2C, D, Idle, XSHUT>, Error<E>)> {
+        let mut chip: VL53L0X<I2C, D, Idle, XSHUT> = self.into_mode();
+        match chip.power_cycle().await {
+            Ok(()) => Ok(chip),
+            Err(e) => Err((chip, e)),
+        }
+    }
+
+    // Power cycles the sensor, then starts ranging again in the same mode
+    pub(super) async fn reset_ranging(&mut self) -> Result<(), Error<E>> {
+        self.power_cycle().await?;
+        self.restart_ranging().await?;
+
+        Ok(())
+    }
+}
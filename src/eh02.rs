@@ -0,0 +1,146 @@
+//! Compatibility layer for embedded-hal 0.2 I2C and delay implementations
+
+use ehal::delay::DelayNs;
+use ehal::i2c::{ErrorKind, ErrorType, I2c, Operation, SevenBitAddress};
+use embedded_hal_0_2::blocking::delay::DelayUs;
+use embedded_hal_0_2::blocking::i2c::{Read, Write, WriteRead};
+
+/// Wraps an embedded-hal 0.2 blocking I2C peripheral so it can be used with [`VL53L0X`](crate::VL53L0X)
+///
+/// `I2cCompat::new(i2c)` accepts any type implementing the 0.2 `Read`, `Write` and `WriteRead`
+/// traits with a common error type.
+#[derive(Debug, Copy, Clone)]
+pub struct I2cCompat<I2C> {
+    inner: I2C,
+}
+
+impl<I2C> I2cCompat<I2C> {
+    /// Wraps an embedded-hal 0.2 I2C peripheral
+    pub fn new(i2c: I2C) -> Self {
+        I2cCompat { inner: i2c }
+    }
+
+    /// Returns the wrapped I2C peripheral
+    pub fn release(self) -> I2C {
+        self.inner
+    }
+}
+
+/// Error of an embedded-hal 0.2 I2C peripheral
+#[derive(Debug, Copy, Clone)]
+pub struct CompatError<E>(pub E);
+
+impl<E> ehal::i2c::Error for CompatError<E>
+where
+    E: core::fmt::Debug,
+{
+    fn kind(&self) -> ErrorKind {
+        ErrorKind::Other
+    }
+}
+
+impl<I2C, E> ErrorType for I2cCompat<I2C>
+where
+    I2C: Read<Error = E> + Write<Error = E> + WriteRead<Error = E>,
+    E: core::fmt::Debug,
+{
+    type Error = CompatError<E>;
+}
+
+impl<I2C, E> I2c<SevenBitAddress> for I2cCompat<I2C>
+where
+    I2C: Read<Error = E> + Write<Error = E> + WriteRead<Error = E>,
+    E: core::fmt::Debug,
+{
+    fn read(&mut self, address: u8, read: &mut [u8]) -> Result<(), Self::Error> {
+        self.inner.read(address, read).map_err(CompatError)
+    }
+
+    fn write(&mut self, address: u8, write: &[u8]) -> Result<(), Self::Error> {
+        self.inner.write(address, write).map_err(CompatError)
+    }
+
+    fn write_read(
+        &mut self,
+        address: u8,
+        write: &[u8],
+        read: &mut [u8],
+    ) -> Result<(), Self::Error> {
+        self.inner
+            .write_read(address, write, read)
+            .map_err(CompatError)
+    }
+
+    fn transaction(
+        &mut self,
+        address: u8,
+        operations: &mut [Operation<'_>],
+    ) -> Result<(), Self::Error> {
+        // the 0.2 traits have no notion of transactions, so adjacent writes are merged into a
+        // single write (the driver never writes more than a register address and a few bytes)
+        let mut buffer = [0u8; 32];
+        let mut len = 0;
+        for operation in operations.iter_mut() {
+            match operation {
+                Operation::Write(bytes) => {
+                    if len + bytes.len() > buffer.len() {
+                        self.write(address, &buffer[..len])?;
+                        len = 0;
+                    }
+                    if bytes.len() > buffer.len() {
+                        self.write(address, bytes)?;
+                    } else {
+                        buffer[len..len + bytes.len()].copy_from_slice(bytes);
+                        len += bytes.len();
+                    }
+                }
+                Operation::Read(read) => {
+                    if len > 0 {
+                        self.write_read(address, &buffer[..len], read)?;
+                        len = 0;
+                    } else {
+                        self.read(address, read)?;
+                    }
+                }
+            }
+        }
+        if len > 0 {
+            self.write(address, &buffer[..len])?;
+        }
+        Ok(())
+    }
+}
+
+/// Wraps an embedded-hal 0.2 blocking delay so it can be used with [`VL53L0X`](crate::VL53L0X)
+///
+/// `DelayCompat::new(delay)` accepts any type implementing the 0.2 `DelayUs<u32>` trait.
+#[derive(Debug, Copy, Clone)]
+pub struct DelayCompat<D> {
+    inner: D,
+}
+
+impl<D> DelayCompat<D> {
+    /// Wraps an embedded-hal 0.2 delay
+    pub fn new(delay: D) -> Self {
+        DelayCompat { inner: delay }
+    }
+
+    /// Returns the wrapped delay
+    pub fn release(self) -> D {
+        self.inner
+    }
+}
+
+impl<D> DelayNs for DelayCompat<D>
+where
+    D: DelayUs<u32>,
+{
+    fn delay_ns(&mut self, ns: u32) {
+        // the 0.2 traits have no nanosecond delay, round up to the next microsecond
+        self.inner.delay_us(ns.div_ceil(1000));
+    }
+
+    fn delay_us(&mut self, us: u32) {
+        self.inner.delay_us(us);
+    }
+}
@@ -1,6 +1,20 @@
 #![no_std]
 
 //! Manages a new VL53L0X, Time-of-Flight I2C laser-ranging module
+//!
+//! The driver is built on the [embedded-hal](https://github.com/rust-embedded/embedded-hal)
+//! 1.0 [`I2c`](ehal::i2c::I2c) and [`DelayNs`](ehal::delay::DelayNs) traits; the delay is used to
+//! sleep between polls of the device. Implementations of the embedded-hal 0.2 blocking I2C and
+//! delay traits can still be used through `eh02::I2cCompat` and `eh02::DelayCompat` when the
+//! `eh02` feature is enabled.
+//!
+//! The ranging mode is tracked in the driver type: single measurements are taken on an [`Idle`]
+//! driver, which `start_continuous` and `start_timed` turn into a [`Continuous`] or [`Timed`] one
+//! offering the continuous readings.
+//!
+//! With the `async` feature, `asynch::VL53L0X` offers the same API on top of the
+//! embedded-hal-async `I2c` and `DelayNs` traits. Both drivers are generated from the same
+//! source, so the register sequences they issue are identical.
 
 #![deny(
     missing_copy_implementations,
@@ -13,31 +27,34 @@
     warnings
 )]
 #![allow(dead_code)]
-#![allow(clippy::uninit_assumed_init, clippy::upper_case_acronyms)]
+#![allow(clippy::upper_case_acronyms)]
+// mode transitions return the driver along with the error
+#![allow(clippy::type_complexity)]
 
-extern crate cast;
 extern crate embedded_hal as ehal;
-extern crate generic_array;
+#[cfg(feature = "async")]
+extern crate embedded_hal_async;
+extern crate maybe_async_cfg;
 extern crate nb;
 
-use cast::u16;
-use core::mem::MaybeUninit;
-use ehal::blocking::i2c::{Read, Write, WriteRead};
-use generic_array::typenum::consts::*;
-use generic_array::{ArrayLength, GenericArray};
+#[cfg(feature = "async")]
+pub mod asynch;
+mod calibration;
+mod device;
+#[cfg(feature = "eh02")]
+pub mod eh02;
+mod limit_check;
+mod mode;
 
-/// Sometimes it's correct (0x29 << 1) instead of 0x29
-const ADDRESS_DEFAULT: u8 = 0x29;
+pub use calibration::{CalibrationData, CalibrationDataError};
+pub use device::{SensorArray, VL53L0X};
+pub use limit_check::LimitCheck;
+pub use mode::{Continuous, Idle, Ranging, Timed};
 
-/// Struct for VL53L0X
-#[derive(Debug, Copy, Clone)]
-pub struct VL53L0X<I2C> {
-    com: I2C,
-    io_mode2v8: bool,
-    stop_variable: u8,
-    measurement_timing_budget_microseconds: u32,
-    address: u8,
-}
+use ehal::i2c::ErrorKind;
+
+/// Default 7-bit I2C address of the device
+const ADDRESS_DEFAULT: u8 = 0x29;
 
 /// Defines errors
 #[derive(Debug, Copy, Clone)]
@@ -48,696 +65,320 @@ pub enum Error<E> {
     BusError(E),
     /// Timeout
     Timeout,
+    /// Error reading or driving a GPIO pin
+    PinError,
+    /// Calibration could not complete, e.g. no valid measurement of the target was taken
+    CalibrationFailed,
+    /// Timing budget is too short for the enabled sequence steps, or too long for the final range
+    /// timeout register
+    InvalidTimingBudget {
+        /// Requested timing budget in microseconds
+        requested: u32,
+        /// Minimum timing budget in microseconds
+        minimum: u32,
+        /// Maximum timing budget in microseconds
+        maximum: u32,
+    },
+    /// Signal rate limit out of the 0 to 511.99 MCPS range
+    InvalidSignalRateLimit,
+    /// Sigma limit out of the 0 to 65535 mm range
+    InvalidSigmaLimit,
+    /// I2C address out of the 0x08 to 0x77 range (returned value is argument)
+    InvalidAddress(u8),
+    /// VCSEL pulse period not supported for the period type (returned value is argument)
+    InvalidVcselPeriod(u8),
+    /// Sequence step timeout not settable, or out of range
+    InvalidSequenceStepTimeout,
+    /// Crosstalk compensation rate out of the 0 to 7.99 MCPS range
+    InvalidXtalkCompensationRate,
+    /// Interrupt thresholds above 8190 mm, or low threshold above the high one
+    InvalidInterruptThresholds,
+    /// Range offset out of the -512 to 511.75 mm range
+    InvalidOffset,
 }
 
-impl<E> From<E> for Error<E> {
-    fn from(error: E) -> Self {
-        Error::BusError(error)
-    }
-}
-
-impl<I2C, E> VL53L0X<I2C>
+impl<E> Error<E>
 where
-    I2C: WriteRead<Error = E> + Write<Error = E> + Read<Error = E>,
+    E: ehal::i2c::Error,
 {
-    /// Creates a sensor with default configuration.
-    ///
-    /// Sometimes it is necessary to shift the address by one place. Use `new()` constructor for this purpose:
-    /// `VL53L0X::new(i2c, 0x29 << 1, true)`
-    pub fn default(i2c: I2C) -> Result<VL53L0X<I2C>, Error<E>> {
-        VL53L0X::new(i2c, ADDRESS_DEFAULT, true)
-    }
-
-    /// Creates a sensor with specific configuration
-    pub fn new(i2c: I2C, address: u8, io_mode2v8: bool) -> Result<VL53L0X<I2C>, Error<E>> {
-        let mut chip = VL53L0X {
-            com: i2c,
-            io_mode2v8,
-            stop_variable: 0,
-            measurement_timing_budget_microseconds: 0,
-            address,
-        };
-
-        let wai = chip.who_am_i()?;
-
-        if wai == 0xEE {
-            chip.init_hardware()?;
-            Ok(chip)
-        } else {
-            Err(Error::InvalidDevice(wai))
+    /// Returns the kind of the underlying bus error, if this error was caused by the bus
+    pub fn kind(&self) -> Option<ErrorKind> {
+        match self {
+            Error::BusError(e) => Some(e.kind()),
+            _ => None,
         }
     }
+}
 
-    fn read_register(&mut self, reg: Register) -> Result<u8, E> {
-        let mut data: [u8; 1] = [0];
-        self.com.write_read(self.address, &[reg as u8], &mut data)?;
-        Ok(data[0])
-    }
-
-    fn read_byte(&mut self, reg: u8) -> Result<u8, E> {
-        let mut data: [u8; 1] = [0];
-        self.com.write_read(self.address, &[reg], &mut data)?;
-        Ok(data[0])
+impl<E> From<E> for Error<E> {
+    fn from(error: E) -> Self {
+        Error::BusError(error)
     }
+}
 
-    fn read_6bytes(&mut self, reg: Register) -> Result<[u8; 6], E> {
-        let buffer: GenericArray<u8, U6> = self.read_registers(reg)?;
-        let mut ret: [u8; 6] = Default::default();
-        ret.copy_from_slice(buffer.as_slice());
-
-        Ok(ret)
-    }
+/// Result of a ranging measurement, as read from the device result registers
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct RangingMeasurement {
+    /// Range in millimeters
+    pub range_mm: u16,
+    /// Range status, tells whether the range can be trusted
+    pub range_status: RangeStatus,
+    /// Device range status code (bits 6:3 of RESULT_RANGE_STATUS), as decoded into `range_status`
+    pub device_range_status: u8,
+    /// Effective number of SPADs that returned a signal, in 8.8 fixed point format
+    pub effective_spad_rtn_count: u16,
+    /// Return signal rate in MCPS (million counts per second), in Q9.7 fixed point format
+    pub signal_rate_mcps: u16,
+    /// Return ambient rate in MCPS, in Q9.7 fixed point format
+    pub ambient_rate_mcps: u16,
+    /// Reference signal rate in MCPS, in Q9.7 fixed point format
+    pub ref_signal_rate_mcps: u16,
+}
 
-    fn read_registers<N>(&mut self, reg: Register) -> Result<GenericArray<u8, N>, E>
-    where
-        N: ArrayLength<u8>,
-    {
-        let mut buffer: GenericArray<u8, N> =
-            unsafe { MaybeUninit::<GenericArray<u8, N>>::uninit().assume_init() };
-
-        {
-            let buffer: &mut [u8] = &mut buffer;
-            const I2C_AUTO_INCREMENT: u8 = 0;
-            self.com
-                .write_read(self.address, &[(reg as u8) | I2C_AUTO_INCREMENT], buffer)?;
+impl RangingMeasurement {
+    // layout of the 12 bytes starting at RESULT_RANGE_STATUS
+    fn from_result_block(block: &[u8; 12]) -> Self {
+        RangingMeasurement {
+            range_mm: u16::from_be_bytes([block[10], block[11]]),
+            range_status: RangeStatus::from_device_range_status((block[0] & 0x78) >> 3),
+            device_range_status: (block[0] & 0x78) >> 3,
+            effective_spad_rtn_count: u16::from_be_bytes([block[2], block[3]]),
+            signal_rate_mcps: u16::from_be_bytes([block[6], block[7]]),
+            ambient_rate_mcps: u16::from_be_bytes([block[8], block[9]]),
+            ref_signal_rate_mcps: u16::from_be_bytes([block[4], block[5]]),
         }
-
-        Ok(buffer)
-    }
-
-    fn read_16bit(&mut self, reg: Register) -> Result<u16, E> {
-        let buffer: GenericArray<u8, U2> = self.read_registers(reg)?;
-        Ok((u16(buffer[0]) << 8) + u16(buffer[1]))
-    }
-
-    fn write_byte(&mut self, reg: u8, byte: u8) -> Result<(), E> {
-        let mut buffer = [0];
-        self.com.write_read(self.address, &[reg, byte], &mut buffer)
-    }
-
-    fn write_register(&mut self, reg: Register, byte: u8) -> Result<(), E> {
-        let mut buffer = [0];
-        self.com
-            .write_read(self.address, &[reg as u8, byte], &mut buffer)
-    }
-
-    fn write_only_register(&mut self, reg: Register, byte: u8) -> Result<(), E> {
-        self.com.write(self.address, &[reg as u8, byte])
     }
+}
 
-    fn write_6bytes(&mut self, reg: Register, bytes: [u8; 6]) -> Result<(), E> {
-        let mut buf: [u8; 6] = [0, 0, 0, 0, 0, 0];
-        self.com.write_read(
-            self.address,
-            &[
-                reg as u8, bytes[0], bytes[1], bytes[2], bytes[3], bytes[4], bytes[5],
-            ],
-            &mut buf,
-        )
-    }
+/// Range status of a measurement
+///
+/// Decoded from the device range status code the same way the ST API does. The limit checks run
+/// by the driver (sigma, range ignore threshold and signal reference clip) are then applied when
+/// enabled, as the ST API does on the host, and report `SigmaFail`, `SignalFail` and
+/// `MinRangeFail` respectively; see [`LimitCheck`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum RangeStatus {
+    /// Range is valid
+    Valid,
+    /// Sigma (standard deviation estimate) is above the limit
+    SigmaFail,
+    /// Return signal is too low to give a reliable range
+    SignalFail,
+    /// Target is below the minimum range, or too close to report a range
+    MinRangeFail,
+    /// Wrap around or phase inconsistency, the range cannot be trusted
+    PhaseFail,
+    /// Laser or VHV failure
+    HardwareFail,
+    /// No target detected; the reported range is typically 8190 or 8191 mm
+    NoUpdate,
+}
 
-    fn write_16bit(&mut self, reg: Register, word: u16) -> Result<(), E> {
-        let mut buffer = [0];
-        let msb = (word >> 8) as u8;
-        let lsb = (word & 0xFF) as u8;
-        self.com
-            .write_read(self.address, &[reg as u8, msb, lsb], &mut buffer)
+impl RangeStatus {
+    fn from_device_range_status(code: u8) -> Self {
+        match code {
+            11 => RangeStatus::Valid,
+            1..=3 => RangeStatus::HardwareFail,
+            6 | 9 => RangeStatus::PhaseFail,
+            8 | 10 => RangeStatus::MinRangeFail,
+            4 | 14 => RangeStatus::SignalFail,
+            7 => RangeStatus::SigmaFail,
+            _ => RangeStatus::NoUpdate,
+        }
     }
+}
 
-    fn write_32bit(&mut self, reg: Register, word: u32) -> Result<(), E> {
-        let mut buffer = [0];
-        let v1 = (word & 0xFF) as u8;
-        let v2 = ((word >> 8) & 0xFF) as u8;
-        let v3 = ((word >> 16) & 0xFF) as u8;
-        let v4 = ((word >> 24) & 0xFF) as u8;
-        self.com
-            .write_read(self.address, &[reg as u8, v1, v2, v3, v4], &mut buffer)
-    }
+/// Ranging profile, as defined by the ST API ranging examples
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum RangingProfile {
+    /// 33 ms timing budget, 0.25 MCPS signal rate limit, 18 mm sigma limit
+    Default,
+    /// Longer range in the dark: 33 ms timing budget, 0.1 MCPS signal rate limit, 60 mm sigma
+    /// limit and 18/14 PCLKs VCSEL pulse periods
+    LongRange,
+    /// 20 ms timing budget, 0.25 MCPS signal rate limit, 32 mm sigma limit
+    HighSpeed,
+    /// 200 ms timing budget, 0.25 MCPS signal rate limit, 18 mm sigma limit
+    HighAccuracy,
+}
 
-    fn set_signal_rate_limit(&mut self, limit: f32) -> Result<bool, E> {
-        if !(0.0..=511.99).contains(&limit) {
-            Ok(false)
-        } else {
-            // Q9.7 fixed point format (9 integer bits, 7 fractional bits)
-            self.write_16bit(
-                Register::FINAL_RANGE_CONFIG_MIN_COUNT_RATE_RTN_LIMIT,
-                (limit * ((1 << 7) as f32)) as u16,
-            )?;
-            Ok(true)
+impl RangingProfile {
+    fn signal_rate_limit_mcps(self) -> f32 {
+        match self {
+            RangingProfile::LongRange => 0.1,
+            _ => 0.25,
         }
     }
 
-    fn get_spad_info(&mut self) -> Result<(u8, u8), Error<E>> {
-        self.write_byte(0x80, 0x01)?;
-        self.write_byte(0xFF, 0x01)?;
-        self.write_byte(0x00, 0x00)?;
-
-        self.write_byte(0xFF, 0x06)?;
-        let mut tmp83 = self.read_byte(0x83)?;
-        self.write_byte(0x83, tmp83 | 0x04)?;
-
-        self.write_byte(0xFF, 0x07)?;
-        self.write_byte(0x81, 0x01)?;
-
-        self.write_byte(0x80, 0x01)?;
-
-        self.write_byte(0x94, 0x6b)?;
-        self.write_byte(0x83, 0x00)?;
-
-        let mut c = 0;
-        while self.read_byte(0x83)? == 0x00 {
-            c += 1;
-            if c == 65535 {
-                return Err(Error::Timeout);
-            }
+    fn sigma_limit_mm(self) -> f32 {
+        match self {
+            RangingProfile::Default | RangingProfile::HighAccuracy => 18.0,
+            RangingProfile::LongRange => 60.0,
+            RangingProfile::HighSpeed => 32.0,
         }
-
-        self.write_byte(0x83, 0x01)?;
-        let tmp = self.read_byte(0x92)?;
-
-        let count: u8 = tmp & 0x7f;
-        let type_is_aperture: u8 = (tmp >> 7) & 0x01;
-
-        self.write_byte(0x81, 0x00)?;
-        self.write_byte(0xFF, 0x06)?;
-
-        tmp83 = self.read_byte(0x83)?;
-        self.write_byte(0x83, tmp83 & !0x04)?;
-
-        self.write_byte(0xFF, 0x01)?;
-        self.write_byte(0x00, 0x01)?;
-
-        self.write_byte(0xFF, 0x00)?;
-        self.write_byte(0x80, 0x00)?;
-
-        Ok((count, type_is_aperture))
     }
 
-    /// Set new address for device
-    pub fn set_device_address(&mut self, address: u8) -> Result<bool, E> {
-        match self.write_only_register(Register::REG_I2C_SLAVE_DEVICE_ADDRESS, address) {
-            Ok(_) => {
-                self.address = address;
-                Ok(true)
-            }
-            Err(e) => Err(e),
+    // (pre-range, final range) periods in PCLKs
+    fn vcsel_pulse_periods(self) -> (u8, u8) {
+        match self {
+            RangingProfile::LongRange => (18, 14),
+            _ => (14, 10),
         }
     }
 
-    /// Start continuous ranging measurements
-    /// Ranging is performed in a continuous way after the API function is called.
-    /// As soon as the measurement is finished, another one is started without delay.
-    /// User has to stop the ranging to return to SW standby. The last measurement is
-    /// completed before stopping
-    ///
-    /// If period_millis is 0, continuous back-to-back mode is used (the sensor takes
-    /// measurements as often as possible); otherwise, continuous timed mode is used,
-    /// with the given inter-measurement period in milliseconds determining how often
-    /// the sensor takes a measurement
-    pub fn start_continuous(&mut self, period_millis: u32) -> Result<(), E> {
-        self.write_byte(0x80, 0x01)?;
-        self.write_byte(0xFF, 0x01)?;
-        self.write_byte(0x00, 0x00)?;
-
-        let sv = self.stop_variable;
-        self.write_byte(0x91, sv)?;
-
-        self.write_byte(0x00, 0x01)?;
-        self.write_byte(0xFF, 0x00)?;
-        self.write_byte(0x80, 0x00)?;
-
-        let mut period_millis = period_millis;
-        if period_millis != 0 {
-            // continuous timed mode
-            let osc_calibrate_value = self.read_16bit(Register::OSC_CALIBRATE_VAL)?;
-
-            if osc_calibrate_value != 0 {
-                period_millis *= osc_calibrate_value as u32;
-            }
-
-            self.write_32bit(Register::SYSTEM_INTERMEASUREMENT_PERIOD, period_millis)?;
-            self.write_register(Register::SYSRANGE_START, 0x04)?;
-        } else {
-            // back-to-back mode
-            self.write_register(Register::SYSRANGE_START, 0x02)?;
+    fn measurement_timing_budget_microseconds(self) -> u32 {
+        match self {
+            RangingProfile::Default | RangingProfile::LongRange => 33_000,
+            RangingProfile::HighSpeed => 20_000,
+            RangingProfile::HighAccuracy => 200_000,
         }
-
-        Ok(())
     }
+}
 
-    /// Stop continuous measurements
-    pub fn stop_continuous(&mut self) -> Result<(), E> {
-        self.write_register(Register::SYSRANGE_START, 0x01)?;
-        self.write_byte(0xFF, 0x01)?;
-        self.write_byte(0x00, 0x00)?;
-        self.write_byte(0x91, 0x00)?;
-        self.write_byte(0x00, 0x01)?;
-        self.write_byte(0xFF, 0x00)?;
-
-        Ok(())
-    }
+/// GPIO1 interrupt mode
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum InterruptMode {
+    /// No interrupt
+    Disabled = 0,
+    /// Interrupt when the range is below the low threshold
+    LevelLow = 1,
+    /// Interrupt when the range is above the high threshold
+    LevelHigh = 2,
+    /// Interrupt when the range is below the low threshold or above the high threshold
+    OutOfWindow = 3,
+    /// Interrupt when a new measurement is ready; set by `new`
+    NewSampleReady = 4,
+}
 
-    /// Reads and returns range measurement in millimiters
-    pub fn read_range_mm(&mut self) -> nb::Result<u16, Error<E>> {
-        match self.read_register(Register::RESULT_INTERRUPT_STATUS) {
-            Ok(r) => {
-                if r.trailing_zeros() >= 3 {
-                    Err(nb::Error::WouldBlock)
-                } else {
-                    let range_err = self.read_16bit(Register::RESULT_RANGE_STATUS_PLUS_10);
-                    let write_err = self.write_register(Register::SYSTEM_INTERRUPT_CLEAR, 0x01);
-                    match (range_err, write_err) {
-                        (Ok(res), Ok(_)) => Ok(res),
-                        (Err(e), _) => Err(nb::Error::Other(Error::from(e))),
-                        (_, Err(e)) => Err(nb::Error::Other(Error::from(e))),
-                    }
-                }
-            }
-            Err(e) => Err(nb::Error::Other(Error::from(e))),
-        }
-    }
+/// GPIO1 interrupt output polarity
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum InterruptPolarity {
+    /// Pin is low while the interrupt is asserted; set by `new`
+    ActiveLow,
+    /// Pin is high while the interrupt is asserted
+    ActiveHigh,
+}
 
-    /// Returns a range reading in millimeters when continuous mode is active
-    pub fn read_range_continuous_millimeters_blocking(&mut self) -> Result<u16, Error<E>> {
-        let mut c = 0;
-        while (self.read_register(Register::RESULT_INTERRUPT_STATUS)?).trailing_zeros() >= 3 {
-            c += 1;
-            if c == 10000 {
-                return Err(Error::Timeout);
-            }
-        }
+/// Reference SPADs (single photon avalanche diodes) selection
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct ReferenceSpads {
+    /// Number of enabled reference SPADs
+    pub count: u8,
+    /// Whether the enabled SPADs are aperture SPADs (true) or non-aperture SPADs (false)
+    pub is_aperture: bool,
+}
 
-        let range_err = self.read_16bit(Register::RESULT_RANGE_STATUS_PLUS_10);
-        self.write_register(Register::SYSTEM_INTERRUPT_CLEAR, 0x01)?;
+/// VHV (voltage high voltage) and phase reference calibration values
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct RefCalibration {
+    /// VHV setting
+    pub vhv_settings: u8,
+    /// Phase calibration
+    pub phase_cal: u8,
+}
 
-        Ok(range_err?)
-    }
+/// Step of the ranging sequence
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum SequenceStep {
+    /// Target centre check
+    Tcc,
+    /// Dynamic SPAD selection
+    Dss,
+    /// Minimum signal rate check
+    Msrc,
+    /// Pre-range
+    PreRange,
+    /// Final range
+    FinalRange,
+}
 
-    /// Returns a single reading in millimeters
-    pub fn read_range_single_millimeters_blocking(&mut self) -> Result<u16, Error<E>> {
-        self.write_byte(0x80, 0x01)?;
-        self.write_byte(0xFF, 0x01)?;
-        self.write_byte(0x00, 0x00)?;
-        let sv = self.stop_variable;
-        self.write_byte(0x91, sv)?;
-        self.write_byte(0x00, 0x01)?;
-        self.write_byte(0xFF, 0x00)?;
-        self.write_byte(0x80, 0x00)?;
-
-        self.write_register(Register::SYSRANGE_START, 0x01)?;
-
-        // wait until start bit has been cleared
-        let mut c = 0;
-        while (self.read_register(Register::SYSRANGE_START)? & 0x01) != 0 {
-            c += 1;
-            if c == 10000 {
-                return Err(Error::Timeout);
-            }
+impl SequenceStep {
+    // SYSTEM_SEQUENCE_CONFIG bit enabling the step
+    fn sequence_config_bit(self) -> u8 {
+        match self {
+            SequenceStep::Tcc => 0x10,
+            SequenceStep::Dss => 0x08,
+            SequenceStep::Msrc => 0x04,
+            SequenceStep::PreRange => 0x40,
+            SequenceStep::FinalRange => 0x80,
         }
-        self.read_range_continuous_millimeters_blocking()
     }
+}
 
-    // Performs a single calibration
-    fn perform_single_ref_calibration(&mut self, vhv_init_byte: u8) -> Result<(), Error<E>> {
-        self.write_register(Register::SYSRANGE_START, 0x01 | vhv_init_byte)?;
-        let mut c = 0;
-        while (self.read_register(Register::RESULT_INTERRUPT_STATUS)?).trailing_zeros() >= 3 {
-            c += 1;
-            if c == 10000 {
-                return Err(Error::Timeout);
-            }
-        }
+/// Enabled steps of the ranging sequence
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct SeqStepEnables {
+    /// Target centre check
+    pub tcc: bool,
+    /// Dynamic SPAD selection
+    pub dss: bool,
+    /// Minimum signal rate check
+    pub msrc: bool,
+    /// Pre-range
+    pub pre_range: bool,
+    /// Final range
+    pub final_range: bool,
+}
 
-        self.write_register(Register::SYSTEM_INTERRUPT_CLEAR, 0x01)?;
-        self.write_register(Register::SYSRANGE_START, 0x00)?;
+/// Timeouts of the ranging sequence steps, as returned by `VL53L0X::get_sequence_step_timeouts`
+///
+/// Timeouts are given in MCLKs (macro periods, whose length depends on the VCSEL pulse period of
+/// the step) and in microseconds. The TCC, DSS and MSRC steps share one timeout, which uses the
+/// pre-range VCSEL pulse period.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct SeqStepTimeouts {
+    pre_range_vcselperiod_pclks: u8,
+    final_range_vcsel_period_pclks: u8,
+    msrc_dss_tcc_mclks: u16,
+    pre_range_mclks: u16,
+    final_range_mclks: u16,
+    msrc_dss_tcc_microseconds: u32,
+    pre_range_microseconds: u32,
+    final_range_microseconds: u32,
+}
 
-        Ok(())
+impl SeqStepTimeouts {
+    /// Pre-range VCSEL pulse period in PCLKs
+    pub fn pre_range_vcsel_period_pclks(&self) -> u8 {
+        self.pre_range_vcselperiod_pclks
     }
 
-    fn init_hardware(&mut self) -> Result<(), Error<E>> {
-        // enable the sensor, sensor uses 1V8 mode for I/O by default; switch to 2V8 mode if necessary
-        if self.io_mode2v8 {
-            let ext_sup_hv = self.read_register(Register::VHV_CONFIG_PAD_SCL_SDA__EXTSUP_HV)?;
-            self.write_register(
-                Register::VHV_CONFIG_PAD_SCL_SDA__EXTSUP_HV,
-                ext_sup_hv | 0x01,
-            )?;
-        }
-
-        // set I2C standard mode
-        self.write_byte(0x88, 0x00)?;
-        self.write_byte(0x80, 0x01)?;
-        self.write_byte(0xFF, 0x01)?;
-        self.write_byte(0x00, 0x00)?;
-        self.stop_variable = self.read_byte(0x91)?;
-        self.write_byte(0x00, 0x01)?;
-        self.write_byte(0xFF, 0x00)?;
-        self.write_byte(0x80, 0x00)?;
-
-        // disable SIGNAL_RATE_MSRC (bit 1) and SIGNAL_RATE_PRE_RANGE (bit 4) limit checks
-        let config = self.read_register(Register::MSRC_CONFIG_CONTROL)?;
-        self.write_register(Register::MSRC_CONFIG_CONTROL, config | 0x12)?;
-
-        // set final range signal rate limit to 0.25 MCPS (million counts per second)
-        self.set_signal_rate_limit(0.25)?;
-
-        self.write_register(Register::SYSTEM_SEQUENCE_CONFIG, 0xFF)?;
-
-        let (spad_count, spad_type_is_aperture) = self.get_spad_info()?;
-
-        // the SPAD map (RefGoodSpadMap) is read by VL53L0X_get_info_from_device() in the API,
-        // but the same data seems to be more easily readable from GLOBAL_CONFIG_SPAD_ENABLES_REF_0
-        // through _6, so read it from there
-        let mut ref_spad_map = self.read_6bytes(Register::GLOBAL_CONFIG_SPAD_ENABLES_REF_0)?;
-
-        self.write_byte(0xFF, 0x01)?;
-        self.write_register(Register::DYNAMIC_SPAD_REF_EN_START_OFFSET, 0x00)?;
-        self.write_register(Register::DYNAMIC_SPAD_NUM_REQUESTED_REF_SPAD, 0x2C)?;
-        self.write_byte(0xFF, 0x00)?;
-        self.write_register(Register::GLOBAL_CONFIG_REF_EN_START_SELECT, 0xB4)?;
-
-        // 12 is the first aperture spad
-        let first_spad_to_enable = if spad_type_is_aperture != 0 { 12 } else { 0 };
-        let mut spads_enabled: u8 = 0;
-
-        for i in 0..48 {
-            if i < first_spad_to_enable || spads_enabled == spad_count {
-                // this bit is lower than the first one that should be enabled, or
-                // (reference_spad_count) bits have already been enabled, so zero this bit
-                ref_spad_map[i / 8] &= !(1 << (i % 8));
-            } else if (ref_spad_map[i / 8] >> (i % 8)) & 0x1 > 0 {
-                spads_enabled += 1;
-            }
-        }
-
-        self.write_6bytes(Register::GLOBAL_CONFIG_SPAD_ENABLES_REF_0, ref_spad_map)?;
-
-        self.write_byte(0xFF, 0x01)?;
-        self.write_byte(0x00, 0x00)?;
-
-        self.write_byte(0xFF, 0x00)?;
-        self.write_byte(0x09, 0x00)?;
-        self.write_byte(0x10, 0x00)?;
-        self.write_byte(0x11, 0x00)?;
-
-        self.write_byte(0x24, 0x01)?;
-        self.write_byte(0x25, 0xFF)?;
-        self.write_byte(0x75, 0x00)?;
-
-        self.write_byte(0xFF, 0x01)?;
-        self.write_byte(0x4E, 0x2C)?;
-        self.write_byte(0x48, 0x00)?;
-        self.write_byte(0x30, 0x20)?;
-
-        self.write_byte(0xFF, 0x00)?;
-        self.write_byte(0x30, 0x09)?;
-        self.write_byte(0x54, 0x00)?;
-        self.write_byte(0x31, 0x04)?;
-        self.write_byte(0x32, 0x03)?;
-        self.write_byte(0x40, 0x83)?;
-        self.write_byte(0x46, 0x25)?;
-        self.write_byte(0x60, 0x00)?;
-        self.write_byte(0x27, 0x00)?;
-        self.write_byte(0x50, 0x06)?;
-        self.write_byte(0x51, 0x00)?;
-        self.write_byte(0x52, 0x96)?;
-        self.write_byte(0x56, 0x08)?;
-        self.write_byte(0x57, 0x30)?;
-        self.write_byte(0x61, 0x00)?;
-        self.write_byte(0x62, 0x00)?;
-        self.write_byte(0x64, 0x00)?;
-        self.write_byte(0x65, 0x00)?;
-        self.write_byte(0x66, 0xA0)?;
-
-        self.write_byte(0xFF, 0x01)?;
-        self.write_byte(0x22, 0x32)?;
-        self.write_byte(0x47, 0x14)?;
-        self.write_byte(0x49, 0xFF)?;
-        self.write_byte(0x4A, 0x00)?;
-
-        self.write_byte(0xFF, 0x00)?;
-        self.write_byte(0x7A, 0x0A)?;
-        self.write_byte(0x7B, 0x00)?;
-        self.write_byte(0x78, 0x21)?;
-
-        self.write_byte(0xFF, 0x01)?;
-        self.write_byte(0x23, 0x34)?;
-        self.write_byte(0x42, 0x00)?;
-        self.write_byte(0x44, 0xFF)?;
-        self.write_byte(0x45, 0x26)?;
-        self.write_byte(0x46, 0x05)?;
-        self.write_byte(0x40, 0x40)?;
-        self.write_byte(0x0E, 0x06)?;
-        self.write_byte(0x20, 0x1A)?;
-        self.write_byte(0x43, 0x40)?;
-
-        self.write_byte(0xFF, 0x00)?;
-        self.write_byte(0x34, 0x03)?;
-        self.write_byte(0x35, 0x44)?;
-
-        self.write_byte(0xFF, 0x01)?;
-        self.write_byte(0x31, 0x04)?;
-        self.write_byte(0x4B, 0x09)?;
-        self.write_byte(0x4C, 0x05)?;
-        self.write_byte(0x4D, 0x04)?;
-
-        self.write_byte(0xFF, 0x00)?;
-        self.write_byte(0x44, 0x00)?;
-        self.write_byte(0x45, 0x20)?;
-        self.write_byte(0x47, 0x08)?;
-        self.write_byte(0x48, 0x28)?;
-        self.write_byte(0x67, 0x00)?;
-        self.write_byte(0x70, 0x04)?;
-        self.write_byte(0x71, 0x01)?;
-        self.write_byte(0x72, 0xFE)?;
-        self.write_byte(0x76, 0x00)?;
-        self.write_byte(0x77, 0x00)?;
-
-        self.write_byte(0xFF, 0x01)?;
-        self.write_byte(0x0D, 0x01)?;
-
-        self.write_byte(0xFF, 0x00)?;
-        self.write_byte(0x80, 0x01)?;
-        self.write_byte(0x01, 0xF8)?;
-
-        self.write_byte(0xFF, 0x01)?;
-        self.write_byte(0x8E, 0x01)?;
-        self.write_byte(0x00, 0x01)?;
-        self.write_byte(0xFF, 0x00)?;
-        self.write_byte(0x80, 0x00)?;
-
-        self.write_register(Register::SYSTEM_INTERRUPT_CONFIG_GPIO, 0x04)?;
-
-        let high = self.read_register(Register::GPIO_HV_MUX_ACTIVE_HIGH)?;
-        self.write_register(Register::GPIO_HV_MUX_ACTIVE_HIGH, high & !0x10)?;
-        self.write_register(Register::SYSTEM_INTERRUPT_CLEAR, 0x01)?;
-
-        self.measurement_timing_budget_microseconds = self.get_measurement_timing_budget()?;
-        self.write_register(Register::SYSTEM_SEQUENCE_CONFIG, 0xE8)?;
-
-        // recalculate timing budget
-        let mtbm = self.measurement_timing_budget_microseconds;
-        self.set_measurement_timing_budget(mtbm)?;
-
-        self.write_register(Register::SYSTEM_SEQUENCE_CONFIG, 0x01)?;
-        self.perform_single_ref_calibration(0x40)?;
-
-        self.write_register(Register::SYSTEM_SEQUENCE_CONFIG, 0x02)?;
-        self.perform_single_ref_calibration(0x00)?;
-
-        // restore the previous sequence config
-        self.write_register(Register::SYSTEM_SEQUENCE_CONFIG, 0xE8)?;
-
-        Ok(())
+    /// Final range VCSEL pulse period in PCLKs
+    pub fn final_range_vcsel_period_pclks(&self) -> u8 {
+        self.final_range_vcsel_period_pclks
     }
 
-    /// Returns WHO_AM_I register
-    pub fn who_am_i(&mut self) -> Result<u8, E> {
-        self.read_register(Register::WHO_AM_I)
+    /// TCC, DSS and MSRC timeout in MCLKs
+    pub fn msrc_dss_tcc_mclks(&self) -> u16 {
+        self.msrc_dss_tcc_mclks
     }
 
-    fn get_vcsel_pulse_period(&mut self, ty: VcselPeriodType) -> Result<u8, E> {
-        match ty {
-            VcselPeriodType::VcselPeriodPreRange => Ok(decode_vcsel_period(
-                self.read_register(Register::PRE_RANGE_CONFIG_VCSEL_PERIOD)?,
-            )),
-            VcselPeriodType::VcselPeriodFinalRange => Ok(decode_vcsel_period(
-                self.read_register(Register::FINAL_RANGE_CONFIG_VCSEL_PERIOD)?,
-            )),
-        }
+    /// Pre-range timeout in MCLKs
+    pub fn pre_range_mclks(&self) -> u16 {
+        self.pre_range_mclks
     }
 
-    fn get_sequence_step_enables(&mut self) -> Result<SeqStepEnables, E> {
-        let sequence_config: u8 = self.read_register(Register::SYSTEM_SEQUENCE_CONFIG)?;
-        Ok(SeqStepEnables {
-            tcc: ((sequence_config >> 4) & 0x1) == 1,
-            dss: ((sequence_config >> 3) & 0x1) == 1,
-            msrc: ((sequence_config >> 2) & 0x1) == 1,
-            pre_range: ((sequence_config >> 6) & 0x1) == 1,
-            final_range: ((sequence_config >> 7) & 0x1) == 1,
-        })
+    /// Final range timeout in MCLKs, excluding the pre-range
+    pub fn final_range_mclks(&self) -> u16 {
+        self.final_range_mclks
     }
 
-    fn get_sequence_step_timeouts(
-        &mut self,
-        enables: &SeqStepEnables,
-    ) -> Result<SeqStepTimeouts, E> {
-        let pre_range_mclks =
-            decode_timeout(self.read_16bit(Register::PRE_RANGE_CONFIG_TIMEOUT_MACROP_HI)?);
-        let mut final_range_mclks =
-            decode_timeout(self.read_16bit(Register::FINAL_RANGE_CONFIG_TIMEOUT_MACROP_HI)?);
-        if enables.pre_range {
-            final_range_mclks -= pre_range_mclks;
-        };
-        let pre_range_vcselperiod_pclks =
-            self.get_vcsel_pulse_period(VcselPeriodType::VcselPeriodPreRange)?;
-        let msrc_dss_tcc_mclks = self.read_register(Register::MSRC_CONFIG_TIMEOUT_MACROP)? + 1;
-        let final_range_vcsel_period_pclks =
-            self.get_vcsel_pulse_period(VcselPeriodType::VcselPeriodFinalRange)?;
-
-        Ok(SeqStepTimeouts {
-            pre_range_vcselperiod_pclks,
-            msrc_dss_tcc_mclks,
-            msrc_dss_tcc_microseconds: timeout_mclks_to_microseconds(
-                msrc_dss_tcc_mclks as u16,
-                pre_range_vcselperiod_pclks,
-            ),
-            pre_range_mclks,
-            pre_range_microseconds: timeout_mclks_to_microseconds(
-                pre_range_mclks,
-                pre_range_vcselperiod_pclks,
-            ),
-            final_range_mclks,
-            final_range_vcsel_period_pclks,
-            final_range_microseconds: timeout_mclks_to_microseconds(
-                final_range_mclks,
-                final_range_vcsel_period_pclks,
-            ),
-        })
+    /// TCC, DSS and MSRC timeout in microseconds
+    pub fn msrc_dss_tcc_microseconds(&self) -> u32 {
+        self.msrc_dss_tcc_microseconds
     }
 
-    fn get_measurement_timing_budget(&mut self) -> Result<u32, E> {
-        let start_overhead: u32 = 1910;
-        let end_overhead: u32 = 960;
-        let msrc_overhead: u32 = 660;
-        let tcc_overhead: u32 = 590;
-        let dss_overhead: u32 = 690;
-        let pre_range_overhead: u32 = 660;
-        let final_range_overhead: u32 = 550;
-
-        let enables = self.get_sequence_step_enables()?;
-        let timeouts = self.get_sequence_step_timeouts(&enables)?;
-
-        // start and end overhead times always present
-        let mut budget_microseconds = start_overhead + end_overhead;
-        if enables.tcc {
-            budget_microseconds += timeouts.msrc_dss_tcc_microseconds + tcc_overhead;
-        }
-        if enables.dss {
-            budget_microseconds += 2 * (timeouts.msrc_dss_tcc_microseconds + dss_overhead);
-        } else if enables.msrc {
-            budget_microseconds += timeouts.msrc_dss_tcc_microseconds + msrc_overhead;
-        }
-        if enables.pre_range {
-            budget_microseconds += timeouts.pre_range_microseconds + pre_range_overhead;
-        }
-        if enables.final_range {
-            budget_microseconds += timeouts.final_range_microseconds + final_range_overhead;
-        }
-
-        Ok(budget_microseconds)
+    /// Pre-range timeout in microseconds
+    pub fn pre_range_microseconds(&self) -> u32 {
+        self.pre_range_microseconds
     }
 
-    /// Set the measurement timing budget in microseconds, which is the time allowed for one measurement;
-    /// the ST API and this library take care of splitting the timing budget among the sub-steps in the
-    /// ranging sequence. A longer timing budget allows for more accurate measurements. Increasing the
-    /// budget by a factor of N decreases the range measurement standard deviation by a factor of sqrt(N).
-    /// Defaults to about 33 milliseconds; the minimum is 20 ms
-    pub fn set_measurement_timing_budget(&mut self, budget_microseconds: u32) -> Result<bool, E> {
-        let start_overhead: u32 = 1320;
-        let end_overhead: u32 = 960;
-        let msrc_overhead: u32 = 660;
-        let tcc_overhead: u32 = 590;
-        let dss_overhead: u32 = 690;
-        let pre_range_overhead: u32 = 660;
-        let final_range_overhead: u32 = 550;
-        let min_timing_budget: u32 = 20000;
-
-        if budget_microseconds < min_timing_budget {
-            return Ok(false);
-        }
-
-        let enables = self.get_sequence_step_enables()?;
-        let timeouts = self.get_sequence_step_timeouts(&enables)?;
-
-        let mut use_budget_microseconds = start_overhead + end_overhead;
-        if enables.tcc {
-            use_budget_microseconds += timeouts.msrc_dss_tcc_microseconds + tcc_overhead;
-        }
-        if enables.dss {
-            use_budget_microseconds += 2 * timeouts.msrc_dss_tcc_microseconds + dss_overhead;
-        } else if enables.msrc {
-            use_budget_microseconds += timeouts.msrc_dss_tcc_microseconds + msrc_overhead;
-        }
-        if enables.pre_range {
-            use_budget_microseconds += timeouts.pre_range_microseconds + pre_range_overhead;
-        }
-        if enables.final_range {
-            use_budget_microseconds += final_range_overhead;
-        }
-
-        // note that the final range timeout is determined by the timing budget and the sum of
-        // all other timeouts within the sequence. If there is no room for the final range timeout,
-        // then an error will be set. Otherwise the remaining time will be applied to the final range
-        if use_budget_microseconds > budget_microseconds {
-            // requested timeout too small
-            return Ok(false);
-        }
-
-        let final_range_timeout_microseconds: u32 = budget_microseconds - use_budget_microseconds;
-
-        // for the final range timeout, the pre-range timeout must be added. To do this both final
-        // and pre-range timeouts must be expressed in macro periods MClks ecause they have different
-        // vcsel periods
-        let mut final_range_timeout_mclks: u16 = timeout_microseconds_to_mclks(
-            final_range_timeout_microseconds,
-            timeouts.final_range_vcsel_period_pclks,
-        ) as u16;
-
-        if enables.pre_range {
-            final_range_timeout_mclks += timeouts.pre_range_mclks;
-        }
-
-        self.write_16bit(
-            Register::FINAL_RANGE_CONFIG_TIMEOUT_MACROP_HI,
-            encode_timeout(final_range_timeout_mclks),
-        )?;
-
-        self.measurement_timing_budget_microseconds = budget_microseconds;
-
-        Ok(true)
+    /// Final range timeout in microseconds, excluding the pre-range
+    pub fn final_range_microseconds(&self) -> u32 {
+        self.final_range_microseconds
     }
 }
 
-struct SeqStepEnables {
-    tcc: bool,
-    dss: bool,
-    msrc: bool,
-    pre_range: bool,
-    final_range: bool,
-}
-
-struct SeqStepTimeouts {
-    pre_range_vcselperiod_pclks: u8,
-    final_range_vcsel_period_pclks: u8,
-    msrc_dss_tcc_mclks: u8,
-    pre_range_mclks: u16,
-    final_range_mclks: u16,
-    msrc_dss_tcc_microseconds: u32,
-    pre_range_microseconds: u32,
-    final_range_microseconds: u32,
-}
-
 fn decode_timeout(register_value: u16) -> u16 {
     ((register_value & 0x00FF) << ((register_value & 0xFF00) >> 8)) + 1
 }
@@ -759,21 +400,109 @@ fn encode_timeout(timeout_mclks: u16) -> u16 {
     (ms_byte << 8) | ((ls_byte & 0xFF) as u16)
 }
 
+// Splits the measurement timing budget among the enabled steps of the ranging sequence, and
+// returns the final range timeout in MCLKs, pre-range included as the final range register holds
+// it, or `Error::InvalidTimingBudget` if the budget does not fit the sequence
+fn final_range_timeout_mclks<E>(
+    enables: &SeqStepEnables,
+    timeouts: &SeqStepTimeouts,
+    budget_microseconds: u32,
+) -> Result<u16, Error<E>> {
+    let start_overhead: u32 = 1320;
+    let end_overhead: u32 = 960;
+    let msrc_overhead: u32 = 660;
+    let tcc_overhead: u32 = 590;
+    let dss_overhead: u32 = 690;
+    let pre_range_overhead: u32 = 660;
+    let final_range_overhead: u32 = 550;
+    let min_timing_budget: u32 = 20000;
+
+    let mut use_budget_microseconds = start_overhead + end_overhead;
+    if enables.tcc {
+        use_budget_microseconds += timeouts.msrc_dss_tcc_microseconds + tcc_overhead;
+    }
+    if enables.dss {
+        use_budget_microseconds += 2 * timeouts.msrc_dss_tcc_microseconds + dss_overhead;
+    } else if enables.msrc {
+        use_budget_microseconds += timeouts.msrc_dss_tcc_microseconds + msrc_overhead;
+    }
+    if enables.pre_range {
+        use_budget_microseconds += timeouts.pre_range_microseconds + pre_range_overhead;
+    }
+    if enables.final_range {
+        use_budget_microseconds += final_range_overhead;
+    }
+
+    // the final range register holds the pre-range timeout as well, in at most 16 bits
+    let mut max_final_range_timeout_mclks = u16::MAX;
+    if enables.pre_range {
+        max_final_range_timeout_mclks -= timeouts.pre_range_mclks;
+    }
+    let max_budget_microseconds = use_budget_microseconds
+        + timeout_mclks_to_microseconds(
+            max_final_range_timeout_mclks,
+            timeouts.final_range_vcsel_period_pclks,
+        );
+
+    // note that the final range timeout is determined by the timing budget and the sum of
+    // all other timeouts within the sequence. If there is no room for the final range timeout,
+    // then an error will be set. Otherwise the remaining time will be applied to the final range
+    let min_budget_microseconds = use_budget_microseconds.max(min_timing_budget);
+    if budget_microseconds < min_budget_microseconds
+        || budget_microseconds > max_budget_microseconds
+    {
+        return Err(Error::InvalidTimingBudget {
+            requested: budget_microseconds,
+            minimum: min_budget_microseconds,
+            maximum: max_budget_microseconds,
+        });
+    }
+
+    let final_range_timeout_microseconds: u32 = budget_microseconds - use_budget_microseconds;
+
+    // for the final range timeout, the pre-range timeout must be added. To do this both final
+    // and pre-range timeouts must be expressed in macro periods MClks ecause they have different
+    // vcsel periods
+    let mut final_range_timeout_mclks: u16 = timeout_microseconds_to_mclks(
+        final_range_timeout_microseconds,
+        timeouts.final_range_vcsel_period_pclks,
+    )
+    .min(max_final_range_timeout_mclks as u32) as u16;
+
+    if enables.pre_range {
+        final_range_timeout_mclks += timeouts.pre_range_mclks;
+    }
+
+    Ok(final_range_timeout_mclks)
+}
+
 fn calc_macro_period(vcsel_period_pclks: u8) -> u32 {
     ((2304u32 * (vcsel_period_pclks as u32) * 1655u32) + 500u32) / 1000u32
 }
 
 fn timeout_mclks_to_microseconds(timeout_period_mclks: u16, vcsel_period_pclks: u8) -> u32 {
-    let macro_period_nanoseconds = calc_macro_period(vcsel_period_pclks);
-    (((timeout_period_mclks as u32) * macro_period_nanoseconds) + (macro_period_nanoseconds / 2))
-        / 1000
+    let macro_period_nanoseconds = calc_macro_period(vcsel_period_pclks) as u64;
+    // at most 65535 MCLKs of 68.6 us, which fits in u32 microseconds once divided
+    ((((timeout_period_mclks as u64) * macro_period_nanoseconds) + (macro_period_nanoseconds / 2))
+        / 1000) as u32
 }
 
+// Saturates at u32::MAX MCLKs, far beyond any timeout the registers can hold
 fn timeout_microseconds_to_mclks(timeout_period_microseconds: u32, vcsel_period_pclks: u8) -> u32 {
-    let macro_period_nanoseconds = calc_macro_period(vcsel_period_pclks);
+    let macro_period_nanoseconds = calc_macro_period(vcsel_period_pclks) as u64;
+
+    let mclks = ((timeout_period_microseconds as u64 * 1000) + (macro_period_nanoseconds / 2))
+        / macro_period_nanoseconds;
+    mclks.min(u32::MAX as u64) as u32
+}
+
+// Q9.7 fixed point format (9 integer bits, 7 fractional bits)
+fn encode_q9_7(value: f32) -> u16 {
+    (value * ((1 << 7) as f32)) as u16
+}
 
-    ((timeout_period_microseconds * 1000) + (macro_period_nanoseconds / 2))
-        / macro_period_nanoseconds
+fn decode_q9_7(value: u16) -> f32 {
+    (value as f32) / ((1 << 7) as f32)
 }
 
 fn decode_vcsel_period(register_value: u8) -> u8 {
@@ -800,6 +529,8 @@ enum Register {
     SYSTEM_INTERRUPT_CONFIG_GPIO = 0x0A,
     GPIO_HV_MUX_ACTIVE_HIGH = 0x84,
     SYSTEM_INTERRUPT_CLEAR = 0x0B,
+    SYSTEM_THRESH_HIGH = 0x0C,
+    SYSTEM_THRESH_LOW = 0x0E,
     RESULT_INTERRUPT_STATUS = 0x13,
     RESULT_RANGE_STATUS = 0x14,
     RESULT_RANGE_STATUS_PLUS_10 = 0x1e,
@@ -813,10 +544,21 @@ enum Register {
     FINAL_RANGE_CONFIG_TIMEOUT_MACROP_LO = 0x72,
     CROSSTALK_COMPENSATION_PEAK_RATE_MCPS = 0x20,
     MSRC_CONFIG_TIMEOUT_MACROP = 0x46,
+    PRE_RANGE_MIN_COUNT_RATE_RTN_LIMIT = 0x64,
+    ALGO_PART_TO_PART_RANGE_OFFSET_MM = 0x28,
+    PRE_RANGE_CONFIG_VALID_PHASE_LOW = 0x56,
+    PRE_RANGE_CONFIG_VALID_PHASE_HIGH = 0x57,
+    FINAL_RANGE_CONFIG_VALID_PHASE_LOW = 0x47,
+    FINAL_RANGE_CONFIG_VALID_PHASE_HIGH = 0x48,
+    GLOBAL_CONFIG_VCSEL_WIDTH = 0x32,
+    ALGO_PHASECAL_CONFIG_TIMEOUT = 0x30,
 }
 
-#[derive(Debug, Copy, Clone)]
-enum VcselPeriodType {
+/// VCSEL (vertical cavity surface emitting laser) period type
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum VcselPeriodType {
+    /// Pre-range step period, 12 to 18 PCLKs
     VcselPeriodPreRange = 0,
+    /// Final range step period, 8 to 14 PCLKs
     VcselPeriodFinalRange = 1,
 }
@@ -0,0 +1,240 @@
+//! Limit checks, used to flag measurements that cannot be trusted
+//!
+//! Some checks are run by the device during ranging, the others are run by the driver on each
+//! measurement it reads, as the ST API does.
+
+use crate::{timeout_microseconds_to_mclks, RangeStatus, RangingMeasurement, SeqStepTimeouts};
+
+/// Limit check, as defined by the ST API
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum LimitCheck {
+    /// Maximum sigma (range standard deviation estimate), in mm; reported as `SigmaFail`.
+    /// Run by the driver
+    SigmaFinalRange = 0,
+    /// Minimum return signal rate in the final range step, in MCPS. Run by the device
+    SignalRateFinalRange = 1,
+    /// Maximum reference signal rate, in MCPS; reported as `MinRangeFail`. Run by the driver
+    SignalRefClip = 2,
+    /// Minimum return signal rate per SPAD, in MCPS; reported as `SignalFail`. Run by the driver
+    RangeIgnoreThreshold = 3,
+    /// Minimum return signal rate in the MSRC (minimum signal rate check) step, in MCPS.
+    /// Run by the device, shares its value with `SignalRatePreRange`
+    SignalRateMsrc = 4,
+    /// Minimum return signal rate in the pre-range step, in MCPS. Run by the device, shares
+    /// its value with `SignalRateMsrc`
+    SignalRatePreRange = 5,
+}
+
+const LIMIT_CHECK_COUNT: usize = 6;
+
+// Limit check state kept by the driver; the MSRC and pre-range checks live on the device only
+#[derive(Debug, Copy, Clone)]
+pub(crate) struct LimitChecks {
+    pub(crate) enables: [bool; LIMIT_CHECK_COUNT],
+    pub(crate) values: [f32; LIMIT_CHECK_COUNT],
+}
+
+impl Default for LimitChecks {
+    fn default() -> Self {
+        LimitChecks {
+            // only the final range signal rate check is enabled by init_hardware()
+            enables: [false, true, false, false, false, false],
+            values: [18.0, 0.25, 35.0, 0.0, 0.0, 0.0],
+        }
+    }
+}
+
+impl LimitChecks {
+    pub(crate) fn enabled(&self, check: LimitCheck) -> bool {
+        self.enables[check as usize]
+    }
+
+    pub(crate) fn value(&self, check: LimitCheck) -> f32 {
+        self.values[check as usize]
+    }
+
+    // Runs the checks done by the driver; as in the ST API, a sigma failure is reported only if
+    // nothing else failed, and a signal failure takes precedence over it. The crosstalk
+    // compensation rate is per SPAD, in MCPS, and 0 when compensation is disabled
+    pub(crate) fn apply(
+        &self,
+        measurement: &mut RangingMeasurement,
+        timeouts: &SeqStepTimeouts,
+        xtalk_compensation_rate_mcps: f32,
+    ) {
+        if self.enabled(LimitCheck::SigmaFinalRange)
+            && measurement.range_status == RangeStatus::Valid
+            && sigma_estimate(measurement, timeouts, xtalk_compensation_rate_mcps)
+                > (self.value(LimitCheck::SigmaFinalRange) * 65536.0) as u32
+        {
+            measurement.range_status = RangeStatus::SigmaFail;
+        }
+
+        if self.enabled(LimitCheck::RangeIgnoreThreshold)
+            && matches!(
+                measurement.range_status,
+                RangeStatus::Valid | RangeStatus::SigmaFail
+            )
+        {
+            let threshold = self.value(LimitCheck::RangeIgnoreThreshold);
+            // effective SPAD count is 8.8, signal rate Q9.7
+            let signal_rate_per_spad = if measurement.effective_spad_rtn_count == 0 {
+                0.0
+            } else {
+                (measurement.signal_rate_mcps as f32) * 2.0
+                    / (measurement.effective_spad_rtn_count as f32)
+            };
+            if threshold != 0.0 && signal_rate_per_spad < threshold {
+                measurement.range_status = RangeStatus::SignalFail;
+            }
+        }
+
+        if self.enabled(LimitCheck::SignalRefClip)
+            && matches!(
+                measurement.range_status,
+                RangeStatus::Valid | RangeStatus::SigmaFail | RangeStatus::SignalFail
+            )
+        {
+            let clip = self.value(LimitCheck::SignalRefClip);
+            let ref_signal_rate = (measurement.ref_signal_rate_mcps as f32) / 128.0;
+            if clip > 0.0 && ref_signal_rate > clip {
+                measurement.range_status = RangeStatus::MinRangeFail;
+            }
+        }
+    }
+}
+
+// Estimates the range sigma in mm (FixPoint1616), as VL53L0X_calc_sigma_estimate() in the ST API
+fn sigma_estimate(
+    measurement: &RangingMeasurement,
+    timeouts: &SeqStepTimeouts,
+    xtalk_compensation_rate_mcps: f32,
+) -> u32 {
+    // expressed in 100ths of a ns, i.e. centi-ns
+    const PULSE_EFFECTIVE_WIDTH_CENTI_NS: u64 = 800;
+    const AMBIENT_EFFECTIVE_WIDTH_CENTI_NS: u64 = 600;
+    // 25 ms, FixPoint1616
+    const DFLT_FINAL_RANGE_INTEGRATION_TIME_MILLISECONDS: u64 = 0x0019_0000;
+    const SIGMA_EST_MAX: u64 = 0x028F_87AE;
+    const SIGMA_EST_RTN_MAX: u64 = 0xF000;
+    const AMB_TO_SIGNAL_RATIO_MAX: u64 = 0xF000_0000 / AMBIENT_EFFECTIVE_WIDTH_CENTI_NS;
+    const PLL_PERIOD_PICOSECONDS: u64 = 1655;
+    // um per 1E-10 seconds
+    const SPEED_OF_LIGHT_IN_AIR: u64 = 2997;
+    const TOF_PER_MM_PICOSECONDS: u64 = 6;
+    const VCSEL_PULSE_WIDTH_PICOSECONDS: u64 = 4700;
+    // 50 kcps, FixPoint1616
+    const MAX_XTALK_KCPS: u64 = 0x0032_0000;
+
+    // per SPAD rate to total rate over the effective SPADs (8.8), in FixPoint1616
+    let xtalk_comp_rate_mcps = ((xtalk_compensation_rate_mcps * 65536.0) as u64
+        * measurement.effective_spad_rtn_count as u64
+        + 0x80)
+        >> 8;
+    let xtalk_comp_rate_kcps = (xtalk_comp_rate_mcps * 1000).min(MAX_XTALK_KCPS);
+
+    // Q9.7 to FixPoint1616; kcps rather than mcps keep the values within FixPoint1616. The
+    // device reports the signal rate with the crosstalk removed, it is added back here
+    let signal_rate_mcps = ((measurement.signal_rate_mcps as u64) << 9) + xtalk_comp_rate_mcps;
+    let ambient_rate_kcps = (((measurement.ambient_rate_mcps as u64) << 9) * 1000) >> 16;
+    let peak_signal_rate_kcps = (signal_rate_mcps * 1000 + 0x8000) >> 16;
+
+    let final_range_mclks = timeout_microseconds_to_mclks(
+        timeouts.final_range_microseconds,
+        timeouts.final_range_vcsel_period_pclks,
+    ) as u64;
+    let pre_range_mclks = timeout_microseconds_to_mclks(
+        timeouts.pre_range_microseconds,
+        timeouts.pre_range_vcselperiod_pclks,
+    ) as u64;
+
+    let vcsel_width: u64 = if timeouts.final_range_vcsel_period_pclks == 8 {
+        2
+    } else {
+        3
+    };
+
+    let mut peak_vcsel_duration_microseconds =
+        vcsel_width * 2048 * (pre_range_mclks + final_range_mclks);
+    peak_vcsel_duration_microseconds = (peak_vcsel_duration_microseconds + 500) / 1000;
+    peak_vcsel_duration_microseconds *= PLL_PERIOD_PICOSECONDS;
+    peak_vcsel_duration_microseconds = (peak_vcsel_duration_microseconds + 500) / 1000;
+
+    // FixPoint1616 >> 8 = FixPoint2408
+    let total_signal_rate_mcps = (signal_rate_mcps + 0x80) >> 8;
+    let vcsel_total_events_rtn =
+        ((total_signal_rate_mcps * peak_vcsel_duration_microseconds + 0x80) >> 8).max(1);
+
+    if peak_signal_rate_kcps == 0 {
+        return SIGMA_EST_MAX as u32;
+    }
+
+    let sigma_estimate_p1 = PULSE_EFFECTIVE_WIDTH_CENTI_NS;
+    let sigma_estimate_p2 = ((ambient_rate_kcps << 16) / peak_signal_rate_kcps)
+        .min(AMB_TO_SIGNAL_RATIO_MAX)
+        * AMBIENT_EFFECTIVE_WIDTH_CENTI_NS;
+    let sigma_estimate_p3 = 2 * isqrt(vcsel_total_events_rtn * 12);
+
+    // share of the signal that is not crosstalk, FixPoint1616
+    let diff1_mcps =
+        (((peak_signal_rate_kcps << 16).saturating_sub(xtalk_comp_rate_kcps)) + 500) / 1000;
+    let diff2_mcps = (((peak_signal_rate_kcps << 16) + xtalk_comp_rate_kcps) + 500) / 1000;
+    let xtalk_correction = ((diff1_mcps << 16) / diff2_mcps.max(1)).min(1 << 16);
+
+    // pulse width multiplier, 1.0 without crosstalk
+    let delta_t_picoseconds = measurement.range_mm as u64 * TOF_PER_MM_PICOSECONDS;
+    let mut pw_mult = (delta_t_picoseconds << 16) / VCSEL_PULSE_WIDTH_PICOSECONDS;
+    pw_mult = (pw_mult * ((1 << 16) - xtalk_correction) + 0x8000) >> 16;
+    pw_mult += 1 << 16;
+    pw_mult >>= 1;
+    pw_mult = (pw_mult * pw_mult) >> 14;
+
+    let sqr1 = (pw_mult * sigma_estimate_p1 + 0x8000) >> 16;
+    let sqr2 = (sigma_estimate_p2 + 0x8000) >> 16;
+    let sqrt_result_centi_ns = isqrt(sqr1 * sqr1 + sqr2 * sqr2) << 16;
+
+    // in m, FixPoint1616
+    let sigma_est_rtn =
+        ((((sqrt_result_centi_ns + 50) / 100) / sigma_estimate_p3 * SPEED_OF_LIGHT_IN_AIR + 5000)
+            / 10000)
+            .min(SIGMA_EST_RTN_MAX);
+
+    // 1 mm at 25 ms, scaled by the final range integration time (including pre-range)
+    let final_range_integration_time_milliseconds =
+        ((timeouts.final_range_microseconds + timeouts.pre_range_microseconds + 500) / 1000).max(1)
+            as u64;
+    let sigma_est_ref = ((isqrt(
+        (DFLT_FINAL_RANGE_INTEGRATION_TIME_MILLISECONDS
+            + final_range_integration_time_milliseconds / 2)
+            / final_range_integration_time_milliseconds,
+    ) << 8)
+        + 500)
+        / 1000;
+
+    let sigma_estimate =
+        1000 * isqrt(sigma_est_rtn * sigma_est_rtn + sigma_est_ref * sigma_est_ref);
+
+    sigma_estimate.min(SIGMA_EST_MAX) as u32
+}
+
+fn isqrt(num: u64) -> u64 {
+    let mut num = num;
+    let mut res: u64 = 0;
+    let mut bit: u64 = 1 << 62;
+
+    while bit > num {
+        bit >>= 2;
+    }
+
+    while bit != 0 {
+        if num >= res + bit {
+            num -= res + bit;
+            res = (res >> 1) + bit;
+        } else {
+            res >>= 1;
+        }
+        bit >>= 2;
+    }
+
+    res
+}
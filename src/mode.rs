@@ -0,0 +1,44 @@
+//! Ranging modes, tracked in the type of the driver
+//!
+//! A driver is created [`Idle`]; `start_continuous` and `start_timed` consume it and return a
+//! [`Continuous`] or [`Timed`] driver, which `stop_continuous` turns back into an idle one. Single
+//! measurements can only be taken while idle, and continuous readings only while ranging. The
+//! methods changing the ranging configuration (sequence steps and timeouts, timing budget, VCSEL
+//! periods, limit checks, interrupt settings) or the calibration are only available while idle,
+//! except `perform_ref_calibration`, which stops ranging for the calibration and starts it again.
+//! The I2C address and the I/O timeout can be changed in any mode.
+//!
+//! A failed transition returns the driver along with the error, in the mode the sensor is left in.
+
+/// No ranging in progress: single measurements can be taken
+#[derive(Debug, Copy, Clone)]
+pub struct Idle;
+
+/// Continuous back-to-back ranging, the sensor takes measurements as often as possible
+#[derive(Debug, Copy, Clone)]
+pub struct Continuous;
+
+/// Continuous timed ranging, the sensor takes a measurement every inter-measurement period
+#[derive(Debug, Copy, Clone)]
+pub struct Timed;
+
+/// Modes in which the sensor is ranging continuously
+pub trait Ranging: sealed::Sealed {}
+
+impl Ranging for Continuous {}
+impl Ranging for Timed {}
+
+pub(crate) mod sealed {
+    pub trait Sealed {
+        // SYSRANGE_START value starting the mode
+        const SYSRANGE_MODE: u8;
+    }
+
+    impl Sealed for super::Continuous {
+        const SYSRANGE_MODE: u8 = 0x02;
+    }
+
+    impl Sealed for super::Timed {
+        const SYSRANGE_MODE: u8 = 0x04;
+    }
+}
@@ -0,0 +1,158 @@
+use core::cell::RefCell;
+
+use embedded_hal_bus::i2c::RefCellDevice;
+use gyuvl53l0x::{Error, SensorArray};
+
+mod common;
+
+use common::{FakeBus, FakeXshut, NoDelay};
+
+type Array<'a, MODE = gyuvl53l0x::Idle> =
+    SensorArray<RefCellDevice<'a, FakeBus>, NoDelay, FakeXshut<'a>, 2, MODE>;
+
+fn bring_up(bus: &RefCell<FakeBus>) -> Array<'_> {
+    SensorArray::new(sensors(bus, [0x30, 0x31]), NoDelay, true)
+        .map_err(|(_, e)| e)
+        .unwrap()
+}
+
+fn sensors(
+    bus: &RefCell<FakeBus>,
+    addresses: [u8; 2],
+) -> [(RefCellDevice<'_, FakeBus>, FakeXshut<'_>, u8); 2] {
+    [0, 1].map(|index| {
+        (
+            RefCellDevice::new(bus),
+            FakeXshut { bus, index },
+            addresses[index],
+        )
+    })
+}
+
+fn addresses(bus: &RefCell<FakeBus>) -> [u8; 2] {
+    [0, 1].map(|index| bus.borrow_mut().sensor(index).address())
+}
+
+// SYSRANGE_START of the sensor at `index`
+fn sysrange_start(bus: &RefCell<FakeBus>, index: usize) -> u8 {
+    bus.borrow_mut().sensor(index).register(0x00)
+}
+
+#[test]
+fn brings_up_sensors_at_their_addresses() {
+    let bus = RefCell::new(FakeBus::new(2));
+    let mut array = bring_up(&bus);
+    assert_eq!(addresses(&bus), [0x30, 0x31]);
+    for sensor in array.sensors() {
+        assert_eq!(
+            sensor.read_range_single_millimeters_blocking().unwrap(),
+            300
+        );
+    }
+}
+
+#[test]
+fn returns_sensors_with_invalid_address() {
+    let bus = RefCell::new(FakeBus::new(2));
+    match SensorArray::new(sensors(&bus, [0x30, 0x30]), NoDelay, true) {
+        Err((sensors, Error::InvalidAddress(0x30))) => assert_eq!(sensors.len(), 2),
+        _ => panic!("expected an invalid address"),
+    }
+}
+
+#[test]
+fn returns_sensors_when_bring_up_fails() {
+    let bus = RefCell::new(FakeBus::new(2));
+    bus.borrow_mut().sensor(1).set_hung(true);
+    match SensorArray::new(sensors(&bus, [0x30, 0x31]), NoDelay, true) {
+        Err((mut sensors, Error::BusError(_))) => {
+            // the sensor brought up before the failure can still be used
+            assert_eq!(
+                sensors[0].read_range_single_millimeters_blocking().unwrap(),
+                300
+            );
+        }
+        _ => panic!("expected a bus error"),
+    }
+}
+
+#[test]
+fn recovers_sensor_after_failed_attempt() {
+    let bus = RefCell::new(FakeBus::new(2));
+    let mut array = bring_up(&bus);
+
+    bus.borrow_mut().sensor(1).set_hung(true);
+    assert!(array.recover(1).is_err());
+    bus.borrow_mut().sensor(1).set_hung(false);
+    array.recover(1).unwrap();
+
+    assert_eq!(addresses(&bus), [0x30, 0x31]);
+    for sensor in array.sensors() {
+        assert_eq!(
+            sensor.read_range_single_millimeters_blocking().unwrap(),
+            300
+        );
+    }
+}
+
+#[test]
+fn stops_started_sensors_when_start_fails() {
+    let bus = RefCell::new(FakeBus::new(2));
+    let array = bring_up(&bus);
+
+    bus.borrow_mut().sensor(1).set_hung(true);
+    let (mut array, _) = match array.start_continuous() {
+        Err(failure) => failure,
+        Ok(_) => panic!("expected a bus error"),
+    };
+    // stopped again
+    assert_eq!(sysrange_start(&bus, 0), 0x00);
+
+    bus.borrow_mut().sensor(1).set_hung(false);
+    array.recover(1).unwrap();
+    let mut array = array.start_continuous().map_err(|(_, e)| e).unwrap();
+    assert_eq!(sysrange_start(&bus, 0), 0x02);
+    assert_eq!(sysrange_start(&bus, 1), 0x02);
+    for sensor in array.sensors() {
+        assert_eq!(sensor.read_range_mm().unwrap(), 300);
+    }
+}
+
+#[test]
+fn stops_all_sensors_when_one_fails_to_stop() {
+    let bus = RefCell::new(FakeBus::new(2));
+    let array = bring_up(&bus).start_timed(50).map_err(|(_, e)| e).unwrap();
+
+    bus.borrow_mut().sensor(1).set_hung(true);
+    let (mut array, _) = match array.stop_continuous() {
+        Err(failure) => failure,
+        Ok(_) => panic!("expected a bus error"),
+    };
+    assert_eq!(sysrange_start(&bus, 0), 0x00);
+
+    bus.borrow_mut().sensor(1).set_hung(false);
+    array.recover(1).unwrap();
+    assert_eq!(addresses(&bus), [0x30, 0x31]);
+    for sensor in array.sensors() {
+        assert_eq!(
+            sensor.read_range_single_millimeters_blocking().unwrap(),
+            300
+        );
+    }
+}
+
+#[test]
+fn recovers_ranging_sensor_in_same_mode() {
+    let bus = RefCell::new(FakeBus::new(2));
+    let mut array: Array<'_, gyuvl53l0x::Timed> =
+        bring_up(&bus).start_timed(50).map_err(|(_, e)| e).unwrap();
+
+    bus.borrow_mut().sensor(1).set_hung(true);
+    assert!(array.recover(1).is_err());
+    bus.borrow_mut().sensor(1).set_hung(false);
+    array.recover(1).unwrap();
+
+    assert_eq!(addresses(&bus), [0x30, 0x31]);
+    assert_eq!(sysrange_start(&bus, 1), 0x04);
+    assert_eq!(array.sensor(1).unwrap().read_range_mm().unwrap(), 300);
+}
@@ -0,0 +1,65 @@
+#![cfg(feature = "async")]
+
+use core::future::Future;
+use core::pin::pin;
+use core::task::{Context, Poll, RawWaker, RawWakerVTable, Waker};
+
+use gyuvl53l0x::asynch::VL53L0X;
+
+mod common;
+
+use common::{FakeSensor, NoDelay, ADDRESS};
+
+// Polls `future` to completion; the fake sensor never makes it wait, so no waker is needed
+fn block_on<F: Future>(future: F) -> F::Output {
+    fn clone(_: *const ()) -> RawWaker {
+        RawWaker::new(core::ptr::null(), &VTABLE)
+    }
+    fn noop(_: *const ()) {}
+    static VTABLE: RawWakerVTable = RawWakerVTable::new(clone, noop, noop, noop);
+
+    let waker = unsafe { Waker::from_raw(RawWaker::new(core::ptr::null(), &VTABLE)) };
+    let mut context = Context::from_waker(&waker);
+    let mut future = pin!(future);
+    loop {
+        if let Poll::Ready(output) = future.as_mut().poll(&mut context) {
+            return output;
+        }
+    }
+}
+
+#[test]
+fn reads_single_range() {
+    let mut bus = FakeSensor::new();
+    block_on(async {
+        let mut sensor = VL53L0X::new(&mut bus, NoDelay, ADDRESS, true)
+            .await
+            .unwrap();
+        assert_eq!(
+            sensor
+                .read_range_single_millimeters_blocking()
+                .await
+                .unwrap(),
+            300
+        );
+    });
+}
+
+#[test]
+fn reads_continuous_range() {
+    let mut bus = FakeSensor::new();
+    block_on(async {
+        let sensor = VL53L0X::new(&mut bus, NoDelay, ADDRESS, true)
+            .await
+            .unwrap();
+        let mut sensor = sensor.start_continuous().await.map_err(|(_, e)| e).unwrap();
+        assert_eq!(
+            sensor
+                .read_range_continuous_millimeters_blocking()
+                .await
+                .unwrap(),
+            300
+        );
+        sensor.stop_continuous().await.map_err(|(_, e)| e).unwrap();
+    });
+}
@@ -0,0 +1,45 @@
+use gyuvl53l0x::{Error, VL53L0X};
+
+mod common;
+
+use common::{FakeSensor, NoDelay, ADDRESS};
+
+#[test]
+fn reads_good_spads_from_nvm_after_mcu_reset() {
+    let mut bus = FakeSensor::new();
+    // the sensor kept power while the MCU was reset: only the 2 SPADs of an earlier selection
+    // are still enabled
+    bus.set_register(0xB0, 0x03);
+    for register in 0xB1..0xB6 {
+        bus.set_register(register, 0x00);
+    }
+    VL53L0X::new(&mut bus, NoDelay, ADDRESS, true).unwrap();
+    // the 5 reference SPADs are taken from all the good ones again
+    assert_eq!(bus.register(0xB0), 0x1F);
+}
+
+#[test]
+fn keeps_offset_when_offset_calibration_fails() {
+    let mut bus = FakeSensor::new();
+    // no valid range to average
+    bus.set_register(0x14, 0x00);
+    let mut sensor = VL53L0X::new(&mut bus, NoDelay, ADDRESS, true).unwrap();
+    sensor.set_offset_calibration_um(12_500).unwrap();
+    match sensor.perform_offset_calibration(100, 5) {
+        Err(Error::CalibrationFailed) => {}
+        _ => panic!("expected a failed calibration"),
+    }
+    assert_eq!(sensor.get_offset_calibration_um().unwrap(), 12_500);
+}
+
+#[test]
+fn rejects_out_of_range_offset() {
+    let mut bus = FakeSensor::new();
+    let mut sensor = VL53L0X::new(&mut bus, NoDelay, ADDRESS, true).unwrap();
+    sensor.set_offset_calibration_um(-512_000).unwrap();
+    assert_eq!(sensor.get_offset_calibration_um().unwrap(), -512_000);
+    match sensor.set_offset_calibration_um(512_000) {
+        Err(Error::InvalidOffset) => {}
+        _ => panic!("expected an invalid offset"),
+    }
+}
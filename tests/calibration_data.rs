@@ -0,0 +1,88 @@
+use gyuvl53l0x::{CalibrationData, CalibrationDataError, RefCalibration, ReferenceSpads};
+
+fn calibration_data() -> CalibrationData {
+    CalibrationData {
+        reference_spads: ReferenceSpads {
+            count: 5,
+            is_aperture: true,
+        },
+        ref_spad_map: [0x00, 0x00, 0xF8, 0x01, 0x00, 0x00],
+        ref_calibration: RefCalibration {
+            vhv_settings: 0x1D,
+            phase_cal: 0x01,
+        },
+        offset_um: -12_500,
+        xtalk_compensation_rate_mcps: 0.25,
+        xtalk_compensation_enabled: true,
+    }
+}
+
+#[test]
+fn round_trips_through_bytes() {
+    let data = calibration_data();
+    assert_eq!(CalibrationData::from_bytes(&data.to_bytes()), Ok(data));
+}
+
+#[test]
+fn keeps_the_version_1_layout() {
+    assert_eq!(
+        calibration_data().to_bytes(),
+        [
+            0x01, // version
+            0x05, 0x01, // reference SPADs
+            0x00, 0x00, 0xF8, 0x01, 0x00, 0x00, // reference SPAD map
+            0x1D, 0x01, // VHV settings and phase calibration
+            0x2C, 0xCF, 0xFF, 0xFF, // offset, -12500 um
+            0x00, 0x00, 0x80, 0x3E, // crosstalk compensation rate, 0.25 MCPS
+            0x01, // crosstalk compensation enabled
+            0x97, // CRC-8 (polynomial 0x07) of the preceding bytes
+        ]
+    );
+}
+
+#[test]
+fn ignores_extra_bytes() {
+    let data = calibration_data();
+    let mut bytes = [0xFF; CalibrationData::SIZE + 3];
+    bytes[..CalibrationData::SIZE].copy_from_slice(&data.to_bytes());
+    assert_eq!(CalibrationData::from_bytes(&bytes), Ok(data));
+}
+
+#[test]
+fn rejects_short_slice() {
+    let bytes = calibration_data().to_bytes();
+    assert_eq!(
+        CalibrationData::from_bytes(&bytes[..CalibrationData::SIZE - 1]),
+        Err(CalibrationDataError::InvalidLength)
+    );
+    assert_eq!(
+        CalibrationData::from_bytes(&[]),
+        Err(CalibrationDataError::InvalidLength)
+    );
+}
+
+#[test]
+fn rejects_unsupported_version() {
+    let mut bytes = calibration_data().to_bytes();
+    bytes[0] = 2;
+    assert_eq!(
+        CalibrationData::from_bytes(&bytes),
+        Err(CalibrationDataError::UnsupportedVersion(2))
+    );
+}
+
+#[test]
+fn rejects_flipped_byte() {
+    let bytes = calibration_data().to_bytes();
+    // every byte but the version, which is checked first
+    for index in 1..CalibrationData::SIZE {
+        let mut corrupted = bytes;
+        corrupted[index] ^= 0x01;
+        assert_eq!(
+            CalibrationData::from_bytes(&corrupted),
+            Err(CalibrationDataError::InvalidChecksum),
+            "byte {}",
+            index
+        );
+    }
+}
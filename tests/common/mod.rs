@@ -0,0 +1,241 @@
+//! Fake sensor shared by the integration tests
+
+// each test file uses only part of it
+#![allow(dead_code)]
+
+use core::cell::RefCell;
+use core::convert::Infallible;
+
+use embedded_hal::delay::DelayNs;
+use embedded_hal::digital::{self, OutputPin};
+use embedded_hal::i2c::{ErrorKind, ErrorType, I2c, NoAcknowledgeSource, Operation};
+
+pub const ADDRESS: u8 = 0x29;
+
+// Register file answering just enough for the driver to initialize and range
+#[derive(Debug)]
+pub struct FakeSensor {
+    registers: [u8; 256],
+    address: u8,
+    powered: bool,
+    hung: bool,
+}
+
+impl FakeSensor {
+    pub fn new() -> Self {
+        let mut registers = [0; 256];
+        // WHO_AM_I
+        registers[0xC0] = 0xEE;
+        // measurement and calibrations always done
+        registers[0x13] = 0x07;
+        // all SPADs enabled, as at power on
+        for register in &mut registers[0xB0..0xB6] {
+            *register = 0xFF;
+        }
+        // VCSEL pulse periods of 14 and 10 PCLKs, as at power on
+        registers[0x50] = 0x06;
+        registers[0x70] = 0x04;
+        // range of 300 mm, valid
+        registers[0x14] = 11 << 3;
+        registers[0x1E] = 0x01;
+        registers[0x1F] = 0x2C;
+        FakeSensor {
+            registers,
+            address: ADDRESS,
+            powered: true,
+            hung: false,
+        }
+    }
+
+    // Whether the sensor acknowledges `address`
+    fn answers(&self, address: u8) -> bool {
+        self.powered && !self.hung && address == self.address
+    }
+
+    pub fn address(&self) -> u8 {
+        self.address
+    }
+
+    // A hung sensor answers no more, until `set_hung(false)`; a power cycle does not help
+    pub fn set_hung(&mut self, hung: bool) {
+        self.hung = hung;
+    }
+
+    // XSHUT: low shuts the sensor down, losing its configuration and address
+    pub fn set_powered(&mut self, powered: bool) {
+        if !powered {
+            let hung = self.hung;
+            *self = FakeSensor::new();
+            self.hung = hung;
+        }
+        self.powered = powered;
+    }
+
+    pub fn register(&self, register: u8) -> u8 {
+        match register {
+            // SPAD info ready
+            0x83 => self.registers[0x83] | 0x01,
+            // NVM word selected by 0x94
+            0x90..=0x93 => self.nvm_word(self.registers[0x94])[register as usize - 0x90],
+            register => self.registers[register as usize],
+        }
+    }
+
+    // NVM: 5 non-aperture reference SPADs, all SPADs good
+    fn nvm_word(&self, address: u8) -> [u8; 4] {
+        match address {
+            0x6b => [0x00, 0x00, 0x05, 0x00],
+            0x24 => [0xFF; 4],
+            0x25 => [0xFF, 0xFF, 0x00, 0x00],
+            _ => [0x00; 4],
+        }
+    }
+
+    pub fn set_register(&mut self, register: u8, value: u8) {
+        match register {
+            // SYSRANGE_START, the start bit is cleared at once
+            0x00 => self.registers[0x00] = value & !0x01,
+            // REG_I2C_SLAVE_DEVICE_ADDRESS
+            0x8A => self.address = value & 0x7F,
+            register => self.registers[register as usize] = value,
+        }
+    }
+}
+
+impl ErrorType for FakeSensor {
+    type Error = ErrorKind;
+}
+
+impl I2c for FakeSensor {
+    fn transaction(
+        &mut self,
+        address: u8,
+        operations: &mut [Operation<'_>],
+    ) -> Result<(), Self::Error> {
+        if !self.answers(address) {
+            return Err(ErrorKind::NoAcknowledge(NoAcknowledgeSource::Address));
+        }
+
+        self.transfer(operations);
+        Ok(())
+    }
+}
+
+impl FakeSensor {
+    fn transfer(&mut self, operations: &mut [Operation<'_>]) {
+        let mut register = None;
+        for operation in operations {
+            match operation {
+                Operation::Write(bytes) => {
+                    for byte in bytes.iter() {
+                        match register {
+                            None => register = Some(*byte),
+                            Some(r) => {
+                                self.set_register(r, *byte);
+                                register = Some(r.wrapping_add(1));
+                            }
+                        }
+                    }
+                }
+                Operation::Read(buffer) => {
+                    let mut r = register.unwrap_or(0);
+                    for byte in buffer.iter_mut() {
+                        *byte = self.register(r);
+                        r = r.wrapping_add(1);
+                    }
+                    register = Some(r);
+                }
+            }
+        }
+    }
+}
+
+// Sensors sharing one bus, each with its XSHUT pin given by `xshut`
+#[derive(Debug)]
+pub struct FakeBus {
+    sensors: Vec<FakeSensor>,
+}
+
+impl FakeBus {
+    // `count` sensors, all shut down
+    pub fn new(count: usize) -> Self {
+        let mut sensors: Vec<FakeSensor> = (0..count).map(|_| FakeSensor::new()).collect();
+        for sensor in &mut sensors {
+            sensor.set_powered(false);
+        }
+        FakeBus { sensors }
+    }
+
+    pub fn sensor(&mut self, index: usize) -> &mut FakeSensor {
+        &mut self.sensors[index]
+    }
+}
+
+impl ErrorType for FakeBus {
+    type Error = ErrorKind;
+}
+
+impl I2c for FakeBus {
+    fn transaction(
+        &mut self,
+        address: u8,
+        operations: &mut [Operation<'_>],
+    ) -> Result<(), Self::Error> {
+        let mut answering = self.sensors.iter_mut().filter(|s| s.answers(address));
+        let sensor = answering
+            .next()
+            .ok_or(ErrorKind::NoAcknowledge(NoAcknowledgeSource::Address))?;
+        if answering.next().is_some() {
+            panic!("several sensors answer at {:#04x}", address);
+        }
+
+        sensor.transfer(operations);
+        Ok(())
+    }
+}
+
+// XSHUT pin of the sensor at `index` on a `FakeBus`
+#[derive(Debug)]
+pub struct FakeXshut<'a> {
+    pub bus: &'a RefCell<FakeBus>,
+    pub index: usize,
+}
+
+impl digital::ErrorType for FakeXshut<'_> {
+    type Error = Infallible;
+}
+
+impl OutputPin for FakeXshut<'_> {
+    fn set_low(&mut self) -> Result<(), Self::Error> {
+        self.bus.borrow_mut().sensor(self.index).set_powered(false);
+        Ok(())
+    }
+
+    fn set_high(&mut self) -> Result<(), Self::Error> {
+        self.bus.borrow_mut().sensor(self.index).set_powered(true);
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct NoDelay;
+
+impl DelayNs for NoDelay {
+    fn delay_ns(&mut self, _ns: u32) {}
+}
+
+#[cfg(feature = "async")]
+impl embedded_hal_async::i2c::I2c for FakeSensor {
+    async fn transaction(
+        &mut self,
+        address: u8,
+        operations: &mut [Operation<'_>],
+    ) -> Result<(), Self::Error> {
+        I2c::transaction(self, address, operations)
+    }
+}
+
+#[cfg(feature = "async")]
+impl embedded_hal_async::delay::DelayNs for NoDelay {
+    async fn delay_ns(&mut self, _ns: u32) {}
+}
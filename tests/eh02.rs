@@ -0,0 +1,101 @@
+#![cfg(feature = "eh02")]
+
+use embedded_hal::i2c::{I2c, Operation};
+use embedded_hal_0_2::blocking::i2c::{Read, Write, WriteRead};
+use gyuvl53l0x::eh02::I2cCompat;
+
+// Transfer made on an embedded-hal 0.2 bus
+#[derive(Debug, PartialEq, Eq)]
+enum Transfer {
+    Write(Vec<u8>),
+    WriteRead(Vec<u8>, usize),
+    Read(usize),
+}
+
+// Records the transfers, reads return 0xAA
+#[derive(Debug, Default)]
+struct RecordingBus {
+    transfers: Vec<Transfer>,
+}
+
+impl Read for RecordingBus {
+    type Error = ();
+
+    fn read(&mut self, _address: u8, buffer: &mut [u8]) -> Result<(), ()> {
+        buffer.fill(0xAA);
+        self.transfers.push(Transfer::Read(buffer.len()));
+        Ok(())
+    }
+}
+
+impl Write for RecordingBus {
+    type Error = ();
+
+    fn write(&mut self, _address: u8, bytes: &[u8]) -> Result<(), ()> {
+        self.transfers.push(Transfer::Write(bytes.to_vec()));
+        Ok(())
+    }
+}
+
+impl WriteRead for RecordingBus {
+    type Error = ();
+
+    fn write_read(&mut self, _address: u8, bytes: &[u8], buffer: &mut [u8]) -> Result<(), ()> {
+        buffer.fill(0xAA);
+        self.transfers
+            .push(Transfer::WriteRead(bytes.to_vec(), buffer.len()));
+        Ok(())
+    }
+}
+
+#[test]
+fn merges_adjacent_writes() {
+    let mut i2c = I2cCompat::new(RecordingBus::default());
+    i2c.transaction(
+        0x29,
+        &mut [Operation::Write(&[0x01]), Operation::Write(&[0x02, 0x03])],
+    )
+    .unwrap();
+    assert_eq!(
+        i2c.release().transfers,
+        [Transfer::Write(vec![0x01, 0x02, 0x03])]
+    );
+}
+
+#[test]
+fn merges_writes_into_following_read() {
+    let mut i2c = I2cCompat::new(RecordingBus::default());
+    let mut buffer = [0; 2];
+    i2c.transaction(
+        0x29,
+        &mut [
+            Operation::Write(&[0x14]),
+            Operation::Write(&[0x15]),
+            Operation::Read(&mut buffer),
+            Operation::Read(&mut []),
+        ],
+    )
+    .unwrap();
+    assert_eq!(buffer, [0xAA, 0xAA]);
+    assert_eq!(
+        i2c.release().transfers,
+        [Transfer::WriteRead(vec![0x14, 0x15], 2), Transfer::Read(0)]
+    );
+}
+
+#[test]
+fn splits_writes_longer_than_buffer() {
+    let mut i2c = I2cCompat::new(RecordingBus::default());
+    i2c.transaction(
+        0x29,
+        &mut [Operation::Write(&[0x01; 20]), Operation::Write(&[0x02; 20])],
+    )
+    .unwrap();
+    assert_eq!(
+        i2c.release().transfers,
+        [
+            Transfer::Write(vec![0x01; 20]),
+            Transfer::Write(vec![0x02; 20])
+        ]
+    );
+}
@@ -0,0 +1,47 @@
+use core::convert::Infallible;
+use core::time::Duration;
+
+use embedded_hal::digital::{ErrorType, InputPin};
+use gyuvl53l0x::{Error, VL53L0X};
+
+mod common;
+
+use common::{FakeSensor, NoDelay, ADDRESS};
+
+// GPIO1 held at a fixed level
+struct FakeGpio1(bool);
+
+impl ErrorType for FakeGpio1 {
+    type Error = Infallible;
+}
+
+impl InputPin for FakeGpio1 {
+    fn is_high(&mut self) -> Result<bool, Self::Error> {
+        Ok(self.0)
+    }
+
+    fn is_low(&mut self) -> Result<bool, Self::Error> {
+        Ok(!self.0)
+    }
+}
+
+#[test]
+fn reads_measurement_on_active_level() {
+    let mut bus = FakeSensor::new();
+    let mut sensor = VL53L0X::new(&mut bus, NoDelay, ADDRESS, true).unwrap();
+    let range = sensor
+        .read_range_single_millimeters_interrupt(&mut FakeGpio1(false))
+        .unwrap();
+    assert_eq!(range, 300);
+}
+
+#[test]
+fn times_out_without_interrupt() {
+    let mut bus = FakeSensor::new();
+    let mut sensor = VL53L0X::new(&mut bus, NoDelay, ADDRESS, true).unwrap();
+    sensor.set_io_timeout(Duration::from_millis(10));
+    match sensor.read_range_single_millimeters_interrupt(&mut FakeGpio1(true)) {
+        Err(Error::Timeout) => {}
+        _ => panic!("expected a timeout"),
+    }
+}
@@ -0,0 +1,58 @@
+use gyuvl53l0x::VL53L0X;
+
+mod common;
+
+use common::{FakeSensor, NoDelay, ADDRESS};
+
+// SYSTEM_INTERMEASUREMENT_PERIOD, as written by the driver
+fn intermeasurement_period(bus: &FakeSensor) -> u32 {
+    u32::from_be_bytes([
+        bus.register(0x04),
+        bus.register(0x05),
+        bus.register(0x06),
+        bus.register(0x07),
+    ])
+}
+
+#[test]
+fn ranges_continuously_then_single() {
+    let mut bus = FakeSensor::new();
+    let sensor = VL53L0X::new(&mut bus, NoDelay, ADDRESS, true).unwrap();
+    let mut sensor = sensor.start_continuous().unwrap();
+    assert_eq!(sensor.read_range_mm().unwrap(), 300);
+    let mut sensor = sensor.stop_continuous().unwrap();
+    assert_eq!(
+        sensor.read_range_single_millimeters_blocking().unwrap(),
+        300
+    );
+}
+
+#[test]
+fn starts_and_stops_timed_ranging() {
+    let mut bus = FakeSensor::new();
+    let sensor = VL53L0X::new(&mut bus, NoDelay, ADDRESS, true).unwrap();
+    let sensor = sensor.start_timed(20).unwrap();
+    sensor.release();
+    // SYSRANGE_START in timed mode
+    assert_eq!(bus.register(0x00), 0x04);
+    assert_eq!(intermeasurement_period(&bus), 20);
+
+    let sensor = VL53L0X::new(&mut bus, NoDelay, ADDRESS, true).unwrap();
+    let sensor = sensor.start_timed(20).unwrap();
+    sensor.stop_continuous().unwrap().release();
+    assert_eq!(bus.register(0x00), 0x00);
+}
+
+#[test]
+fn calibrates_while_ranging_with_same_period() {
+    let mut bus = FakeSensor::new();
+    let sensor = VL53L0X::new(&mut bus, NoDelay, ADDRESS, true).unwrap();
+    let mut sensor = sensor.start_timed(50).unwrap();
+    sensor.perform_ref_calibration().unwrap();
+    assert_eq!(sensor.read_range_mm().unwrap(), 300);
+    sensor.release();
+
+    // ranging again in timed mode, with the same period
+    assert_eq!(bus.register(0x00), 0x04);
+    assert_eq!(intermeasurement_period(&bus), 50);
+}
@@ -0,0 +1,75 @@
+use core::cell::RefCell;
+use core::convert::Infallible;
+
+use embedded_hal::digital::{ErrorType, OutputPin};
+use embedded_hal_bus::i2c::RefCellDevice;
+use gyuvl53l0x::VL53L0X;
+
+mod common;
+
+use common::{FakeBus, FakeSensor, FakeXshut, NoDelay, ADDRESS};
+
+// XSHUT pin recording the levels it is driven to
+#[derive(Debug)]
+struct RecordingXshut<'a>(&'a RefCell<Vec<bool>>);
+
+impl ErrorType for RecordingXshut<'_> {
+    type Error = Infallible;
+}
+
+impl OutputPin for RecordingXshut<'_> {
+    fn set_low(&mut self) -> Result<(), Self::Error> {
+        self.0.borrow_mut().push(false);
+        Ok(())
+    }
+
+    fn set_high(&mut self) -> Result<(), Self::Error> {
+        self.0.borrow_mut().push(true);
+        Ok(())
+    }
+}
+
+#[test]
+fn power_cycles_sensor_at_creation() {
+    let levels = RefCell::new(Vec::new());
+    let mut bus = FakeSensor::new();
+    VL53L0X::new_with_xshut(&mut bus, NoDelay, RecordingXshut(&levels), ADDRESS, true).unwrap();
+    assert_eq!(*levels.borrow(), [false, true]);
+}
+
+#[test]
+fn hardware_reset_stops_ranging() {
+    let levels = RefCell::new(Vec::new());
+    let mut bus = FakeSensor::new();
+    let sensor =
+        VL53L0X::new_with_xshut(&mut bus, NoDelay, RecordingXshut(&levels), ADDRESS, true).unwrap();
+    let sensor = sensor.start_continuous().unwrap();
+    let mut sensor = sensor.hardware_reset().unwrap();
+    assert_eq!(*levels.borrow(), [false, true, false, true]);
+    assert_eq!(
+        sensor.read_range_single_millimeters_blocking().unwrap(),
+        300
+    );
+}
+
+#[test]
+fn keeps_address_after_failed_reset() {
+    let bus = RefCell::new(FakeBus::new(1));
+    let xshut = FakeXshut {
+        bus: &bus,
+        index: 0,
+    };
+    let mut sensor =
+        VL53L0X::new_with_xshut(RefCellDevice::new(&bus), NoDelay, xshut, 0x30, true).unwrap();
+
+    bus.borrow_mut().sensor(0).set_hung(true);
+    assert!(sensor.hardware_reset().is_err());
+    bus.borrow_mut().sensor(0).set_hung(false);
+    sensor.hardware_reset().unwrap();
+
+    assert_eq!(bus.borrow_mut().sensor(0).address(), 0x30);
+    assert_eq!(
+        sensor.read_range_single_millimeters_blocking().unwrap(),
+        300
+    );
+}
@@ -0,0 +1,70 @@
+use core::cell::RefCell;
+use std::sync::Mutex;
+
+use embedded_hal::i2c::{ErrorKind, I2c};
+use embedded_hal_bus::i2c::{CriticalSectionDevice, MutexDevice, RefCellDevice};
+use gyuvl53l0x::VL53L0X;
+
+mod common;
+
+use common::{FakeSensor, NoDelay, ADDRESS};
+
+#[test]
+fn ranges_through_borrowed_bus() {
+    let mut bus = FakeSensor::new();
+    let mut sensor = VL53L0X::new(&mut bus, NoDelay, ADDRESS, true).unwrap();
+    assert_eq!(
+        sensor.read_range_single_millimeters_blocking().unwrap(),
+        300
+    );
+    sensor.release();
+
+    // the bus can be used again once the driver is released
+    assert_eq!(bus.register(0xC0), 0xEE);
+}
+
+#[test]
+fn ranges_through_ref_cell_device() {
+    let bus = RefCell::new(FakeSensor::new());
+    let mut sensor = VL53L0X::new(RefCellDevice::new(&bus), NoDelay, ADDRESS, true).unwrap();
+    assert_eq!(
+        sensor.read_range_single_millimeters_blocking().unwrap(),
+        300
+    );
+
+    // another device on the same bus
+    let mut other = RefCellDevice::new(&bus);
+    let mut wai = [0];
+    other.write_read(ADDRESS, &[0xC0], &mut wai).unwrap();
+    assert_eq!(wai, [0xEE]);
+}
+
+#[test]
+fn ranges_through_critical_section_device() {
+    let bus = critical_section::Mutex::new(RefCell::new(FakeSensor::new()));
+    let mut sensor =
+        VL53L0X::new(CriticalSectionDevice::new(&bus), NoDelay, ADDRESS, true).unwrap();
+    assert_eq!(
+        sensor.read_range_single_millimeters_blocking().unwrap(),
+        300
+    );
+}
+
+#[test]
+fn ranges_through_mutex_device() {
+    let bus = Mutex::new(FakeSensor::new());
+    let mut sensor = VL53L0X::new(MutexDevice::new(&bus), NoDelay, ADDRESS, true).unwrap();
+    assert_eq!(
+        sensor.read_range_single_millimeters_blocking().unwrap(),
+        300
+    );
+}
+
+#[test]
+fn returns_bus_error_without_device() {
+    let mut bus = FakeSensor::new();
+    match VL53L0X::new(&mut bus, NoDelay, 0x30, true) {
+        Err(gyuvl53l0x::Error::BusError(ErrorKind::NoAcknowledge(_))) => {}
+        _ => panic!("expected a bus error"),
+    }
+}
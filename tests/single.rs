@@ -0,0 +1,30 @@
+use core::cell::RefCell;
+
+use embedded_hal_bus::i2c::RefCellDevice;
+use gyuvl53l0x::VL53L0X;
+
+mod common;
+
+use common::{FakeSensor, NoDelay, ADDRESS};
+
+#[test]
+fn polls_single_measurement() {
+    let mut bus = FakeSensor::new();
+    let mut sensor = VL53L0X::new(&mut bus, NoDelay, ADDRESS, true).unwrap();
+    sensor.start_single().unwrap();
+    let measurement = nb::block!(sensor.poll_single()).unwrap();
+    assert_eq!(measurement.range_mm, 300);
+}
+
+#[test]
+fn polls_single_measurement_until_ready() {
+    let bus = RefCell::new(FakeSensor::new());
+    let mut sensor = VL53L0X::new(RefCellDevice::new(&bus), NoDelay, ADDRESS, true).unwrap();
+    // RESULT_INTERRUPT_STATUS, no new sample yet
+    bus.borrow_mut().set_register(0x13, 0x00);
+    sensor.start_single().unwrap();
+    assert!(matches!(sensor.poll_single(), Err(nb::Error::WouldBlock)));
+
+    bus.borrow_mut().set_register(0x13, 0x07);
+    assert_eq!(sensor.poll_single().unwrap().range_mm, 300);
+}
@@ -0,0 +1,95 @@
+use gyuvl53l0x::{Error, SequenceStep, VcselPeriodType, VL53L0X};
+
+mod common;
+
+use common::{FakeSensor, NoDelay, ADDRESS};
+
+#[test]
+fn sets_long_msrc_timeout() {
+    let mut bus = FakeSensor::new();
+    let mut sensor = VL53L0X::new(&mut bus, NoDelay, ADDRESS, true).unwrap();
+    sensor.set_measurement_timing_budget(200_000).unwrap();
+    sensor
+        .set_sequence_step_timeout(SequenceStep::Msrc, 20_000)
+        .unwrap();
+    let timeouts = sensor.get_sequence_step_timeouts().unwrap();
+    assert_eq!(timeouts.msrc_dss_tcc_mclks(), 256);
+}
+
+#[test]
+fn rejects_pre_range_timeout_longer_than_budget() {
+    let mut bus = FakeSensor::new();
+    let mut sensor = VL53L0X::new(&mut bus, NoDelay, ADDRESS, true).unwrap();
+    match sensor.set_sequence_step_timeout(SequenceStep::PreRange, 100_000) {
+        Err(Error::InvalidTimingBudget { .. }) => {}
+        _ => panic!("expected an invalid timing budget"),
+    }
+}
+
+#[test]
+fn rejects_out_of_range_pre_range_timeout() {
+    let mut bus = FakeSensor::new();
+    let mut sensor = VL53L0X::new(&mut bus, NoDelay, ADDRESS, true).unwrap();
+    match sensor.set_sequence_step_timeout(SequenceStep::PreRange, 5_000_000) {
+        Err(Error::InvalidSequenceStepTimeout) => {}
+        _ => panic!("expected an invalid sequence step timeout"),
+    }
+}
+
+#[test]
+fn rejects_too_long_timing_budget() {
+    let mut bus = FakeSensor::new();
+    let mut sensor = VL53L0X::new(&mut bus, NoDelay, ADDRESS, true).unwrap();
+    match sensor.set_measurement_timing_budget(5_000_000) {
+        Err(Error::InvalidTimingBudget {
+            requested, maximum, ..
+        }) => assert!(maximum < requested),
+        _ => panic!("expected an invalid timing budget"),
+    }
+}
+
+#[test]
+fn keeps_timing_budget_with_new_vcsel_period() {
+    let mut bus = FakeSensor::new();
+    let mut sensor = VL53L0X::new(&mut bus, NoDelay, ADDRESS, true).unwrap();
+    sensor.set_measurement_timing_budget(200_000).unwrap();
+    let budget = sensor.measurement_timing_budget().unwrap();
+    sensor
+        .set_vcsel_pulse_period(VcselPeriodType::VcselPeriodFinalRange, 14)
+        .unwrap();
+    assert_eq!(
+        sensor
+            .get_vcsel_pulse_period(VcselPeriodType::VcselPeriodFinalRange)
+            .unwrap(),
+        14
+    );
+    // the final range register keeps 8 significant bits
+    let new_budget = sensor.measurement_timing_budget().unwrap();
+    assert!(
+        new_budget.abs_diff(budget) < budget / 100,
+        "{} != {}",
+        new_budget,
+        budget
+    );
+}
+
+#[test]
+fn rejects_vcsel_period_too_short_for_timing_budget() {
+    let mut bus = FakeSensor::new();
+    let mut sensor = VL53L0X::new(&mut bus, NoDelay, ADDRESS, true).unwrap();
+    sensor.set_measurement_timing_budget(2_400_000).unwrap();
+    let budget = sensor.measurement_timing_budget().unwrap();
+    match sensor.set_vcsel_pulse_period(VcselPeriodType::VcselPeriodFinalRange, 8) {
+        Err(Error::InvalidTimingBudget { .. }) => {}
+        _ => panic!("expected an invalid timing budget"),
+    }
+
+    // nothing changed
+    assert_eq!(
+        sensor
+            .get_vcsel_pulse_period(VcselPeriodType::VcselPeriodFinalRange)
+            .unwrap(),
+        10
+    );
+    assert_eq!(sensor.measurement_timing_budget().unwrap(), budget);
+}